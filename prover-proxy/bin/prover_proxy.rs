@@ -1,9 +1,29 @@
+use alloy_primitives::B256;
 use anyhow::Result;
-use clap::Parser;
-use jsonrpc_http_server::ServerBuilder;
+use clap::{Parser, Subcommand};
+use jsonrpc_http_server::{hyper, ServerBuilder};
 use kroma_prover_proxy::{
-    interface::{Rpc, RpcImpl}, utils::block_on, DEFAULT_NETWORK_RPC_URL, DEFAULT_PROOF_STORE_PATH, FAULT_PROOF_ELF, VERIFICATION_KEY_HASH, VERIFYING_KEY
+    archive::ProofArchive,
+    cache::DEFAULT_PROOF_CACHE_CAPACITY,
+    callbacks::{spawn_callback_dispatcher, CallbackDispatcher},
+    crypto::KeyRing,
+    health::HealthCheck,
+    interface::{CallerMeta, Rpc, RpcImpl, RpcMetrics},
+    metrics,
+    middleware::ProxyMiddleware,
+    migrations::{find_undecodable_proofs, list_legacy_request_ids, run_migrations},
+    proof_db::ProofDB,
+    rest::RestApi,
+    scrub::{spawn_scrubber, Verification},
+    subscriptions::spawn_proof_watcher,
+    uploads::UploadStore,
+    utils::block_on,
+    witness_generator::WitnessGenerator,
+    DEFAULT_NETWORK_RPC_URL, DEFAULT_PROOF_STORE_PATH, DEFAULT_UPLOAD_PATH, FAULT_PROOF_ELF,
+    MAX_REQUEST_BODY_SIZE, VERIFICATION_KEY_HASH, VERIFYING_KEY,
 };
+use sp1_sdk::network::NetworkClient;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -13,6 +33,121 @@ struct Args {
 
     #[clap(short, long = "data", default_value = DEFAULT_PROOF_STORE_PATH)]
     data_path: String,
+
+    /// Directory of chunked witness uploads in progress.
+    #[clap(long = "uploads", default_value = DEFAULT_UPLOAD_PATH)]
+    upload_path: String,
+
+    /// JSON-RPC endpoint of the witness generator, from which the witness of a `requestProve`
+    /// without one is fetched. A witness must be given if it is not set.
+    #[clap(long = "witness-generator-url", env = "WITNESS_GENERATOR_URL")]
+    witness_generator_url: Option<String>,
+
+    /// Number of threads which submit queued requests to the SP1 network.
+    #[clap(long = "submit-workers", default_value = "4")]
+    submit_workers: usize,
+
+    /// Endpoint of the WebSocket server, which also serves `subscribeProof`.
+    #[clap(long = "ws-endpoint", default_value = "0.0.0.0:3032")]
+    ws_endpoint: String,
+
+    /// Interval between status queries of the requests which have subscribers.
+    #[clap(long = "watch-interval-secs", default_value = "5")]
+    watch_interval_secs: u64,
+
+    /// Endpoint of the gRPC server, which is not started if it is not set.
+    #[cfg(feature = "grpc")]
+    #[clap(long = "grpc-endpoint", env = "GRPC_ENDPOINT")]
+    grpc_endpoint: Option<String>,
+
+    /// Secret which signs callbacks; `callback_url`s are rejected if it is not set.
+    #[clap(long = "callback-secret", env = "CALLBACK_HMAC_SECRET", hide_env_values = true)]
+    callback_secret: Option<String>,
+
+    /// Interval between deliveries of the callback outbox.
+    #[clap(long = "callback-interval-secs", default_value = "10")]
+    callback_interval_secs: u64,
+
+    /// Interval between scrubs of the proof store; 0 scrubs only at startup.
+    #[clap(long = "scrub-interval-secs", default_value = "21600")]
+    scrub_interval_secs: u64,
+
+    /// Number of stored proofs verified against the program's verifying key per scrub, in turn;
+    /// the others are only checked against their checksums.
+    #[clap(long = "scrub-verify-batch", default_value = "16")]
+    scrub_verify_batch: usize,
+
+    /// Verifies every stored proof on each scrub instead of a batch.
+    #[clap(long)]
+    scrub_verify_all: bool,
+
+    /// File of the key ring which encrypts the proof store. Falls back to
+    /// `PROOF_STORE_ENCRYPTION_KEYS`, and the store is not encrypted if neither is set.
+    #[clap(long = "encryption-key-file")]
+    encryption_key_file: Option<PathBuf>,
+
+    /// Number of ready-to-serve proofs kept in memory; 0 disables the cache.
+    #[clap(long = "proof-cache-size", default_value_t = DEFAULT_PROOF_CACHE_CAPACITY)]
+    proof_cache_size: usize,
+
+    /// Archive of completed proofs, either `s3://<bucket>[/<prefix>]` or `file://<directory>`.
+    #[clap(long = "archive-url", env = "PROOF_ARCHIVE_URL")]
+    archive_url: Option<String>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Apply pending proof store migrations and exit.
+    Migrate {
+        /// Only report what would be migrated.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Re-encrypt every record of the proof store with the active key and exit.
+    RotateKeys {
+        /// Only report how many records would be re-encrypted.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Discard the request of the hashes, so that the next `requestProve` proves them again, and
+    /// exit. The discarded request is kept in the history.
+    Discard { l2_hash: B256, l1_head_hash: B256 },
+}
+
+fn migrate(proof_db: &ProofDB, client: Option<&NetworkClient>, dry_run: bool) -> Result<()> {
+    // NOTE(Ethan): the proofs of a v0 store can only be found by the ids of their requests.
+    let legacy_request_ids = match (proof_db.schema_version()?, client) {
+        (0, Some(client)) => list_legacy_request_ids(client).unwrap_or_else(|e| {
+            tracing::warn!("Failed to list the requests of the SP1 network: {:?}", e);
+            Vec::new()
+        }),
+        (0, None) => {
+            tracing::warn!("SP1_PRIVATE_KEY is not set, so v0 proofs are upgraded on first read");
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+    let reports = run_migrations(proof_db, &legacy_request_ids, dry_run)?;
+    if reports.is_empty() {
+        tracing::info!("The proof store is up to date: v{}", proof_db.schema_version()?);
+    }
+    for report in reports {
+        tracing::info!("{}", report);
+    }
+
+    let undecodable = find_undecodable_proofs(proof_db);
+    if !undecodable.is_empty() {
+        tracing::warn!(
+            "{} stored proof(s) cannot be decoded by the current SP1 version: {:?}",
+            undecodable.len(),
+            undecodable
+        );
+    }
+
+    Ok(())
 }
 
 fn main() -> Result<()> {
@@ -21,25 +156,138 @@ fn main() -> Result<()> {
 
     let args = Args::parse();
 
-    let sp1_private_key =
-        std::env::var("SP1_PRIVATE_KEY").expect("SP1_PRIVATE_KEY must be set for remote proving");
-    let rpc_impl = RpcImpl::new(&args.data_path, &sp1_private_key, DEFAULT_NETWORK_RPC_URL);
-    
+    let key_ring = KeyRing::load(args.encryption_key_file.as_deref())?;
+    if let Some(key_ring) = &key_ring {
+        tracing::info!("The proof store is encrypted with the key {:?}", key_ring.active_key_id());
+    }
+    let archive = args.archive_url.as_deref().map(ProofArchive::from_url).transpose()?;
+    let uploads = UploadStore::new(&args.upload_path, key_ring.clone());
+    let witness_generator =
+        args.witness_generator_url.as_deref().map(WitnessGenerator::new).transpose()?;
+    let proof_db = Arc::new(
+        ProofDB::new(&args.data_path)
+            .with_key_ring(key_ring)
+            .with_proof_cache(args.proof_cache_size)
+            .with_archive(archive),
+    );
+    // NOTE(Ethan): the commands write the store directly, so they refuse to run while a server
+    // holds it, and a server does not start while a command runs.
+    let _store_lock = proof_db.lock()?;
+
+    let sp1_private_key = std::env::var("SP1_PRIVATE_KEY").ok();
+    let client =
+        sp1_private_key.as_deref().map(|key| NetworkClient::new(key, DEFAULT_NETWORK_RPC_URL));
+    match args.command {
+        Some(Command::Migrate { dry_run }) => return migrate(&proof_db, client.as_ref(), dry_run),
+        Some(Command::RotateKeys { dry_run }) => {
+            migrate(&proof_db, client.as_ref(), dry_run)?;
+            let affected = proof_db.rotate_encryption(dry_run)?;
+            let verb = if dry_run { "would re-encrypt" } else { "re-encrypted" };
+            tracing::info!("Key rotation {} {} record(s)", verb, affected);
+            return Ok(());
+        }
+        Some(Command::Discard { l2_hash, l1_head_hash }) => {
+            migrate(&proof_db, client.as_ref(), false)?;
+            match proof_db.discard_request(&l2_hash, &l1_head_hash)? {
+                Some(request_id) => tracing::info!("Discarded the request {:?}", request_id),
+                None => tracing::info!("There is no request of the given hashes"),
+            }
+            let history = proof_db.request_history(&l2_hash, &l1_head_hash);
+            tracing::info!("History of the hashes: {:?}", history);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    let sp1_private_key = sp1_private_key.expect("SP1_PRIVATE_KEY must be set for remote proving");
+    let rpc_impl = RpcImpl::with_proof_db(proof_db, &sp1_private_key, DEFAULT_NETWORK_RPC_URL)
+        .with_callbacks(args.callback_secret.is_some())
+        .with_uploads(uploads)
+        .with_witness_generator(witness_generator);
+    migrate(&rpc_impl.proof_db, Some(&rpc_impl.client), false)?;
+    if let Err(e) = rpc_impl.proof_db.prune_request_ids() {
+        tracing::error!("Failed to prune the request index: {:?}", e);
+    }
+    metrics::count_requests(&rpc_impl.proof_db);
+
     block_on(async {
-        let vk_hash = rpc_impl.client.register_program(&VERIFYING_KEY, FAULT_PROOF_ELF).await.unwrap();
+        let vk_hash =
+            rpc_impl.client.register_program(&VERIFYING_KEY, FAULT_PROOF_ELF).await.unwrap();
         tracing::info!("The program’s key was retrieved from the network: {:?}", vk_hash);
+        rpc_impl.set_program_registered();
     });
-    
-    let mut io = jsonrpc_core::IoHandler::new();
-    io.extend_with(rpc_impl.to_delegate());
-    
+    let health = Arc::new(HealthCheck::new(rpc_impl.clone()));
+    let verification = if args.scrub_verify_all {
+        Verification::All
+    } else {
+        Verification::Batch(args.scrub_verify_batch)
+    };
+    let scrubber = spawn_scrubber(
+        rpc_impl.proof_db.clone(),
+        Duration::from_secs(args.scrub_interval_secs),
+        verification,
+    );
+    // NOTE(Ethan): the scrubber stops after its first scrub if it has no interval.
+    if args.scrub_interval_secs > 0 {
+        health.workers.register("scrubber", scrubber);
+    }
+
+    for submitter in rpc_impl.spawn_submitters(args.submit_workers) {
+        health.workers.register("submitter", submitter);
+    }
+    let watcher =
+        spawn_proof_watcher(rpc_impl.clone(), Duration::from_secs(args.watch_interval_secs));
+    health.workers.register("proof_watcher", watcher);
+    if let Some(secret) = &args.callback_secret {
+        let dispatcher = CallbackDispatcher::new(secret.as_bytes())?;
+        let interval = Duration::from_secs(args.callback_interval_secs);
+        let callback_dispatcher = spawn_callback_dispatcher(rpc_impl.clone(), dispatcher, interval);
+        health.workers.register("callback_dispatcher", callback_dispatcher);
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_endpoint) = &args.grpc_endpoint {
+        tracing::info!("Starting gRPC server at {}", grpc_endpoint);
+        let interval = Duration::from_secs(args.watch_interval_secs);
+        let grpc_server = kroma_prover_proxy::grpc::spawn_grpc_server(
+            rpc_impl.clone(),
+            grpc_endpoint.parse().unwrap(),
+            interval,
+        );
+        health.workers.register("grpc_server", grpc_server);
+    }
+
+    let build_io = || {
+        let mut io = jsonrpc_core::MetaIoHandler::with_middleware(RpcMetrics);
+        io.extend_with(rpc_impl.clone().to_delegate());
+        io
+    };
+
+    tracing::info!("Starting WebSocket server at {}", args.ws_endpoint);
+    let _ws_server = jsonrpc_ws_server::ServerBuilder::with_meta_extractor(
+        build_io(),
+        |context: &jsonrpc_ws_server::RequestContext| CallerMeta::from_ws_context(context),
+    )
+    .max_payload(MAX_REQUEST_BODY_SIZE)
+    .start(&args.ws_endpoint.parse().unwrap())
+    .unwrap();
+
     tracing::info!("Starting Prover at {}", args.endpoint);
     tracing::info!("Program Key: {:#?}", VERIFICATION_KEY_HASH.to_string());
-    let server = ServerBuilder::new(io)
-        .threads(3)
-        .max_request_body_size(200 * 1024 * 1024)
-        .start_http(&args.endpoint.parse().unwrap())
-        .unwrap();
+    let io = build_io();
+    let middleware = ProxyMiddleware::default()
+        .with_rest_api(RestApi::new(rpc_impl.clone()))
+        .with_health(health)
+        .with_metrics()
+        .with_rpc(Arc::new(build_io()));
+    let server = ServerBuilder::with_meta_extractor(io, |request: &hyper::Request<hyper::Body>| {
+        CallerMeta::from_request(request)
+    })
+    .request_middleware(middleware)
+    .threads(3)
+    .max_request_body_size(MAX_REQUEST_BODY_SIZE)
+    .start_http(&args.endpoint.parse().unwrap())
+    .unwrap();
 
     server.wait();
 
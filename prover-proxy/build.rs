@@ -0,0 +1,4 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/prover_proxy.proto").expect("Failed to compile the protos");
+}
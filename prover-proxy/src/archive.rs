@@ -0,0 +1,63 @@
+use anyhow::{anyhow, bail, Result};
+use object_store::{
+    aws::AmazonS3Builder, local::LocalFileSystem, path::Path, ObjectStore, PutPayload,
+};
+use std::sync::Arc;
+
+use crate::utils::block_on;
+
+/// An object storage which keeps completed proofs beyond the local proof store, so that they
+/// survive the loss of a node and can be shared by several proxies.
+pub struct ProofArchive {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+}
+
+impl ProofArchive {
+    /// Opens the archive at `url`, which is either `s3://<bucket>[/<prefix>]` or
+    /// `file://<directory>`. An S3 archive takes its endpoint and credentials from the standard
+    /// `AWS_*` environment variables, e.g. `AWS_ENDPOINT` and `AWS_ALLOW_HTTP` for MinIO.
+    pub fn from_url(url: &str) -> Result<Self> {
+        if let Some(location) = url.strip_prefix("s3://") {
+            let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+            let store = AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .build()
+                .map_err(|e| anyhow!("Failed to open the archive {}: {}", url, e))?;
+            return Ok(Self { store: Arc::new(store), prefix: Path::from(prefix) });
+        }
+        if let Some(directory) = url.strip_prefix("file://") {
+            std::fs::create_dir_all(directory)?;
+            let store = LocalFileSystem::new_with_prefix(directory)
+                .map_err(|e| anyhow!("Failed to open the archive {}: {}", url, e))?;
+            return Ok(Self { store: Arc::new(store), prefix: Path::default() });
+        }
+        bail!("Unsupported archive url: {}", url)
+    }
+
+    fn path(&self, name: &str) -> Path {
+        Path::from(format!("{}/{}", self.prefix, name).trim_start_matches('/'))
+    }
+
+    pub fn put(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.path(name);
+        block_on(async { self.store.put(&path, PutPayload::from(data)).await })
+            .map_err(|e| anyhow!("Failed to archive {}: {}", path, e))?;
+        Ok(())
+    }
+
+    /// Returns `None` if there is no object named `name`.
+    pub fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path(name);
+        let result = block_on(async {
+            match self.store.get(&path).await {
+                Ok(result) => result.bytes().await.map(Some),
+                Err(object_store::Error::NotFound { .. }) => Ok(None),
+                Err(e) => Err(e),
+            }
+        });
+        result
+            .map(|bytes| bytes.map(|bytes| bytes.to_vec()))
+            .map_err(|e| anyhow!("Failed to read {} from the archive: {}", path, e))
+    }
+}
@@ -0,0 +1,48 @@
+use alloy_primitives::B256;
+use lru::LruCache;
+use std::{num::NonZeroUsize, sync::Mutex};
+
+use crate::{metrics, types::ProofResult};
+
+pub const DEFAULT_PROOF_CACHE_CAPACITY: usize = 128;
+
+/// A bounded cache of ready-to-serve `ProofResult`s by request id, which saves reading a proof
+/// from the disk and hex-encoding it on every poll.
+pub struct ProofCache {
+    inner: Option<Mutex<LruCache<B256, ProofResult>>>,
+}
+
+impl ProofCache {
+    /// Creates a cache which holds up to `capacity` results. A zero `capacity` disables it.
+    pub fn new(capacity: usize) -> Self {
+        Self { inner: NonZeroUsize::new(capacity).map(|cap| Mutex::new(LruCache::new(cap))) }
+    }
+
+    pub fn get(&self, request_id: &B256) -> Option<ProofResult> {
+        let inner = self.inner.as_ref()?;
+        let result = inner.lock().unwrap().get(request_id).cloned();
+        match result {
+            Some(_) => metrics::PROOF_CACHE_HITS.inc(),
+            None => metrics::PROOF_CACHE_MISSES.inc(),
+        }
+        result
+    }
+
+    pub fn put(&self, request_id: B256, result: ProofResult) {
+        if let Some(inner) = &self.inner {
+            inner.lock().unwrap().put(request_id, result);
+        }
+    }
+
+    pub fn invalidate(&self, request_id: &B256) {
+        if let Some(inner) = &self.inner {
+            inner.lock().unwrap().pop(request_id);
+        }
+    }
+}
+
+impl Default for ProofCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROOF_CACHE_CAPACITY)
+    }
+}
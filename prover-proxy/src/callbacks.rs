@@ -0,0 +1,173 @@
+use alloy_primitives::B256;
+use anyhow::{anyhow, bail, Result};
+use hmac::{Hmac, Mac};
+use jsonrpc_core::Result as JsonResult;
+use kroma_zkvm_common::types::preprocessing;
+use sha2::Sha256;
+use std::{thread, time::Duration};
+
+use crate::{
+    interface::{Rpc, RpcImpl},
+    proof_db::{PendingCallback, ProofDB},
+    types::{ProofResult, RequestProofResult},
+    utils::now_secs,
+};
+
+/// The header which carries `sha256=<hex encoded HMAC-SHA256 of the body>`.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+pub const MAX_CALLBACK_ATTEMPTS: u32 = 10;
+static MIN_BACKOFF_SECS: u64 = 5;
+static MAX_BACKOFF_SECS: u64 = 60 * 60;
+static CALLBACK_TIMEOUT_SECS: u64 = 30;
+
+/// Returns the hex encoded HMAC-SHA256 of `body`.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes a key of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Returns the delay before the next delivery after `attempts` failed ones.
+pub fn backoff_secs(attempts: u32) -> u64 {
+    MIN_BACKOFF_SECS.saturating_mul(1 << attempts.min(16)).min(MAX_BACKOFF_SECS)
+}
+
+/// Delivers the callbacks of finished requests from the outbox in `ProofDB`.
+pub struct CallbackDispatcher {
+    secret: Vec<u8>,
+    client: reqwest::blocking::Client,
+}
+
+impl CallbackDispatcher {
+    pub fn new(secret: &[u8]) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(CALLBACK_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| anyhow!("Failed to build the callback client: {}", e))?;
+        Ok(Self { secret: secret.to_vec(), client })
+    }
+
+    /// Posts `body` to `url` along with its signature.
+    pub fn send(&self, url: &str, body: &[u8]) -> Result<()> {
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, format!("sha256={}", sign(&self.secret, body)))
+            .body(body.to_vec())
+            .send()
+            .map_err(|e| anyhow!("Failed to call {}: {}", url, e))?;
+        if !response.status().is_success() {
+            bail!("{} responded with {}", url, response.status());
+        }
+        Ok(())
+    }
+
+    /// Delivers every due callback whose request is finished, and reschedules the failed ones with
+    /// an exponential backoff. `fetch` returns the result of a request, which may query the SP1
+    /// network. A request which failed before it reached the SP1 network is reported as `Failed`.
+    /// Returns the number of delivered callbacks.
+    pub fn dispatch(
+        &self,
+        proof_db: &ProofDB,
+        fetch: impl Fn(&B256) -> JsonResult<RequestProofResult>,
+    ) -> usize {
+        let now = now_secs();
+        let mut delivered = 0;
+        for mut callback in proof_db.pending_callbacks() {
+            if callback.next_attempt_at > now {
+                continue;
+            }
+            let result = match (callback.failed, callback.request_id) {
+                (true, _) => Ok(failed_result(&callback)),
+                (false, Some(request_id)) => {
+                    fetch(&request_id).map_err(|e| anyhow!("Failed to fetch the request: {:?}", e))
+                }
+                // The request has not reached the SP1 network yet.
+                (false, None) => continue,
+            };
+            let result = result.and_then(|result| {
+                if !result.proof_result.request_status.is_final() {
+                    return Ok(None);
+                }
+                let body = serde_json::to_vec(&result)
+                    .map_err(|e| anyhow!("Failed to serialize the result: {}", e))?;
+                self.send(&callback.url, &body).map(Some)
+            });
+
+            match result {
+                Ok(None) => {}
+                Ok(Some(())) => {
+                    tracing::info!(
+                        "Delivered a callback of {:?}, {:?}",
+                        callback.l2_hash,
+                        callback.l1_head_hash
+                    );
+                    delivered += 1;
+                    if let Err(e) = proof_db.remove_callback(&callback) {
+                        tracing::error!("Failed to remove a delivered callback: {:?}", e);
+                    }
+                }
+                Err(e) => self.reschedule(proof_db, &mut callback, now, e),
+            }
+        }
+        delivered
+    }
+
+    fn reschedule(
+        &self,
+        proof_db: &ProofDB,
+        callback: &mut PendingCallback,
+        now: u64,
+        error: anyhow::Error,
+    ) {
+        callback.attempts += 1;
+        let result = if callback.attempts >= MAX_CALLBACK_ATTEMPTS {
+            tracing::error!(
+                "Gave up a callback of {:?}, {:?} after {} attempts: {:?}",
+                callback.l2_hash,
+                callback.l1_head_hash,
+                callback.attempts,
+                error
+            );
+            proof_db.remove_callback(callback)
+        } else {
+            tracing::warn!(
+                "Failed to deliver a callback of {:?}, {:?} ({} attempts): {:?}",
+                callback.l2_hash,
+                callback.l1_head_hash,
+                callback.attempts,
+                error
+            );
+            callback.next_attempt_at = now + backoff_secs(callback.attempts);
+            proof_db.update_callback(callback)
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to update the callback outbox: {:?}", e);
+        }
+    }
+}
+
+fn failed_result(callback: &PendingCallback) -> RequestProofResult {
+    let (_, _, user_req_id) =
+        preprocessing(&callback.l2_hash.to_string(), &callback.l1_head_hash.to_string()).unwrap();
+    RequestProofResult::new(
+        &callback.l2_hash,
+        &callback.l1_head_hash,
+        ProofResult::failed(user_req_id),
+    )
+}
+
+/// Delivers the callbacks every `interval` in a background thread.
+pub fn spawn_callback_dispatcher(
+    rpc_impl: RpcImpl,
+    dispatcher: CallbackDispatcher,
+    interval: Duration,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || loop {
+        dispatcher.dispatch(&rpc_impl.proof_db, |request_id| {
+            rpc_impl.get_proof_by_request_id(request_id.to_string())
+        });
+        thread::sleep(interval);
+    })
+}
@@ -0,0 +1,98 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, bail, Result};
+use std::{collections::HashMap, path::Path};
+
+/// The environment variable which holds the key ring when no key file is given.
+pub const ENCRYPTION_KEYS_ENV: &str = "PROOF_STORE_ENCRYPTION_KEYS";
+
+/// An encrypted value along with what is needed to decrypt it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ciphertext {
+    pub key_id: String,
+    pub nonce: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+/// Keys for the authenticated encryption of stored values.
+///
+/// The key ring is written as `<key_id>:<hex encoded 32 bytes>` entries separated by newlines or
+/// commas. The first entry encrypts new values, and the others are only kept to decrypt values
+/// written before a key rotation.
+#[derive(Clone)]
+pub struct KeyRing {
+    active_key_id: String,
+    ciphers: HashMap<String, Aes256Gcm>,
+}
+
+impl KeyRing {
+    pub fn parse(keys: &str) -> Result<Self> {
+        let mut active_key_id = None;
+        let mut ciphers = HashMap::new();
+        for entry in keys.split(['\n', ',']).map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (key_id, key) = entry
+                .split_once(':')
+                .ok_or_else(|| anyhow!("A key must be given as <key_id>:<hex>"))?;
+            let key = hex::decode(key.trim().trim_start_matches("0x"))
+                .map_err(|e| anyhow!("Failed to decode the key {:?}: {}", key_id, e))?;
+            if key.len() != 32 {
+                bail!("The key {:?} must be 32 bytes, but it is {} bytes", key_id, key.len());
+            }
+            let key_id = key_id.trim().to_string();
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+            if ciphers.insert(key_id.clone(), cipher).is_some() {
+                bail!("The key {:?} is given more than once", key_id);
+            }
+            active_key_id.get_or_insert(key_id);
+        }
+
+        let active_key_id = active_key_id.ok_or_else(|| anyhow!("The key ring is empty"))?;
+        Ok(Self { active_key_id, ciphers })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Loads the key ring from `path` if given, or from `ENCRYPTION_KEYS_ENV`. Returns `None` when
+    /// neither is set, in which case values are stored in plaintext.
+    pub fn load(path: Option<&Path>) -> Result<Option<Self>> {
+        match path {
+            Some(path) => Self::from_file(path).map(Some),
+            None => match std::env::var(ENCRYPTION_KEYS_ENV) {
+                Ok(keys) => Self::parse(&keys).map(Some),
+                Err(_) => Ok(None),
+            },
+        }
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    /// Encrypts `data` with the active key. `aad` is authenticated but not encrypted, and must be
+    /// given again to decrypt.
+    pub fn encrypt(&self, data: &[u8], aad: &[u8]) -> Result<Ciphertext> {
+        let cipher = &self.ciphers[&self.active_key_id];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let data = cipher
+            .encrypt(&nonce, Payload { msg: data, aad })
+            .map_err(|e| anyhow!("Failed to encrypt: {}", e))?;
+        Ok(Ciphertext { key_id: self.active_key_id.clone(), nonce: nonce.to_vec(), data })
+    }
+
+    pub fn decrypt(&self, ciphertext: &Ciphertext, aad: &[u8]) -> Result<Vec<u8>> {
+        let cipher = self
+            .ciphers
+            .get(&ciphertext.key_id)
+            .ok_or_else(|| anyhow!("The key {:?} is not in the key ring", ciphertext.key_id))?;
+        if ciphertext.nonce.len() != 12 {
+            bail!("Invalid nonce length: {}", ciphertext.nonce.len());
+        }
+        cipher
+            .decrypt(Nonce::from_slice(&ciphertext.nonce), Payload { msg: &ciphertext.data, aad })
+            .map_err(|e| anyhow!("Failed to decrypt with the key {:?}: {}", ciphertext.key_id, e))
+    }
+}
@@ -8,6 +8,9 @@ pub enum ProverErrorCode {
     ProofGenerationFailed,
     InvalidInputHash,
     SP1NetworkError,
+    RequestNotFound,
+    InvalidWitness,
+    StoreError,
 }
 
 impl ProverErrorCode {
@@ -16,6 +19,9 @@ impl ProverErrorCode {
             ProverErrorCode::InvalidInputHash => 1000,
             ProverErrorCode::SP1NetworkError => 2000,
             ProverErrorCode::ProofGenerationFailed => 3000,
+            ProverErrorCode::RequestNotFound => 4000,
+            ProverErrorCode::InvalidWitness => 5000,
+            ProverErrorCode::StoreError => 6000,
         }
     }
 
@@ -24,6 +30,9 @@ impl ProverErrorCode {
             ProverErrorCode::InvalidInputHash => String::from("Invalid parameters"),
             ProverErrorCode::SP1NetworkError => String::from("SP1 network error"),
             ProverErrorCode::ProofGenerationFailed => String::from("Proof generation failed"),
+            ProverErrorCode::RequestNotFound => String::from("Request not found"),
+            ProverErrorCode::InvalidWitness => String::from("Invalid witness"),
+            ProverErrorCode::StoreError => String::from("Proof store error"),
         }
     }
 }
@@ -34,6 +43,9 @@ impl From<i64> for ProverErrorCode {
             1000 => ProverErrorCode::InvalidInputHash,
             2000 => ProverErrorCode::SP1NetworkError,
             3000 => ProverErrorCode::ProofGenerationFailed,
+            4000 => ProverErrorCode::RequestNotFound,
+            5000 => ProverErrorCode::InvalidWitness,
+            6000 => ProverErrorCode::StoreError,
             _ => panic!("not supported code: {:?}", code),
         }
     }
@@ -90,6 +102,12 @@ impl ProverError {
         JsonError::from(self)
     }
 
+    /// Returns the `JsonError` with the code in its `data`, so that the REST and gRPC APIs can
+    /// answer with a fitting status.
+    pub fn to_json_error_with_code(&self) -> JsonError {
+        JsonError { data: Some(self.code.code().into()), ..JsonError::from(self) }
+    }
+
     pub fn proof_generation_failed(msg: Option<String>) -> Self {
         let code = ProverErrorCode::ProofGenerationFailed;
         let msg = match msg {
@@ -108,4 +126,19 @@ impl ProverError {
         let code = ProverErrorCode::SP1NetworkError;
         Self::new(code.clone(), Some(msg))
     }
+
+    pub fn request_not_found(msg: String) -> Self {
+        let code = ProverErrorCode::RequestNotFound;
+        Self::new(code.clone(), Some(msg))
+    }
+
+    pub fn invalid_witness(msg: String) -> Self {
+        let code = ProverErrorCode::InvalidWitness;
+        Self::new(code.clone(), Some(msg))
+    }
+
+    pub fn store_error(msg: String) -> Self {
+        let code = ProverErrorCode::StoreError;
+        Self::new(code.clone(), Some(msg))
+    }
 }
@@ -0,0 +1,378 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use jsonrpc_core::{Error as JsonError, Result as JsonResult};
+use jsonrpc_http_server::hyper::StatusCode;
+use kroma_zkvm_common::types::preprocessing;
+use std::{
+    net::SocketAddr,
+    pin::Pin,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tonic::{transport::Server, Code, Request, Response, Status};
+
+use crate::{
+    interface::{CallerMeta, Rpc, RpcImpl, CALLER_HEADER},
+    metrics,
+    rest::status_of,
+    types::{
+        ProofResult, RequestOptions, RequestResult, SpecResult, StatusResult, WitnessEncoding,
+        WitnessInput, WitnessResult,
+    },
+    MAX_DECOMPRESSED_SIZE,
+};
+
+pub mod proto {
+    tonic::include_proto!("kroma.prover_proxy.v1");
+}
+
+use proto::{
+    prove_request,
+    prover_proxy_server::{ProverProxy, ProverProxyServer},
+    ProofKey, ProofResponse, ProveRequest, RequestResponse, RequestStatus, SpecRequest,
+    SpecResponse, StatusResponse,
+};
+
+impl From<RequestResult> for RequestStatus {
+    fn from(result: RequestResult) -> Self {
+        match result {
+            RequestResult::None => RequestStatus::None,
+            RequestResult::Processing => RequestStatus::Processing,
+            RequestResult::Completed => RequestStatus::Completed,
+            RequestResult::Failed => RequestStatus::Failed,
+            RequestResult::Cancelled => RequestStatus::Cancelled,
+        }
+    }
+}
+
+impl From<RequestStatus> for RequestResult {
+    fn from(status: RequestStatus) -> Self {
+        match status {
+            RequestStatus::None => RequestResult::None,
+            RequestStatus::Processing => RequestResult::Processing,
+            RequestStatus::Completed => RequestResult::Completed,
+            RequestStatus::Failed => RequestResult::Failed,
+            RequestStatus::Cancelled => RequestResult::Cancelled,
+        }
+    }
+}
+
+impl From<proto::WitnessResult> for WitnessResult {
+    fn from(result: proto::WitnessResult) -> Self {
+        Self {
+            status: result.status().into(),
+            program_key: result.program_key,
+            witness: result.witness,
+        }
+    }
+}
+
+impl From<proto::WitnessEncoding> for WitnessEncoding {
+    fn from(encoding: proto::WitnessEncoding) -> Self {
+        match encoding {
+            proto::WitnessEncoding::Hex => WitnessEncoding::Hex,
+            proto::WitnessEncoding::Base64 => WitnessEncoding::Base64,
+            proto::WitnessEncoding::Zstd => WitnessEncoding::Zstd,
+            proto::WitnessEncoding::Gzip => WitnessEncoding::Gzip,
+        }
+    }
+}
+
+impl From<proto::RequestOptions> for RequestOptions {
+    fn from(options: proto::RequestOptions) -> Self {
+        Self {
+            witness_encoding: Some(options.witness_encoding().into()),
+            callback_url: options.callback_url,
+            force: options.force,
+            priority: options.priority,
+            deadline: options.deadline,
+            idempotency_key: options.idempotency_key,
+            labels: options.labels.into_iter().collect(),
+        }
+    }
+}
+
+impl From<SpecResult> for SpecResponse {
+    fn from(spec: SpecResult) -> Self {
+        Self {
+            version: spec.version,
+            sp1_version: spec.sp1_version,
+            program_key: spec.program_key,
+            api_version: spec.api_version,
+            proof_modes: spec.proof_modes,
+            programs: spec.programs,
+            backends: spec.backends,
+            max_witness_size: spec.max_witness_size,
+            fulfillment_strategy: spec.fulfillment_strategy,
+            queue_depth: spec.queue_depth,
+            program_registered: spec.program_registered,
+        }
+    }
+}
+
+impl From<StatusResult> for StatusResponse {
+    fn from(status: StatusResult) -> Self {
+        Self {
+            request_id: status.request_id.map(|request_id| request_id.to_string()),
+            status: RequestStatus::from(status.status.unwrap_or(RequestResult::None)).into(),
+            created_at: status.created_at,
+            updated_at: status.updated_at,
+            estimated_completion_at: status.estimated_completion_at,
+            deadline: status.deadline,
+            at_risk: status.at_risk,
+        }
+    }
+}
+
+impl From<ProofResult> for ProofResponse {
+    fn from(proof: ProofResult) -> Self {
+        Self {
+            request_id: proof.request_id,
+            status: RequestStatus::from(proof.request_status).into(),
+            program_key: proof.program_key,
+            public_values: proof.public_values,
+            proof: proof.proof,
+        }
+    }
+}
+
+/// Returns the witness and the options of `requestProve` for those of a `ProveRequest`. The
+/// witness bytes are passed on base64 encoded, so they are read as `BASE64` unless they are
+/// compressed.
+pub fn prove_input(
+    witness_input: Option<prove_request::WitnessInput>,
+    options: Option<proto::RequestOptions>,
+) -> (Option<WitnessInput>, Option<RequestOptions>) {
+    let mut options = options.map(RequestOptions::from);
+    let witness = match witness_input {
+        Some(prove_request::WitnessInput::Witness(witness)) => Some(WitnessInput::Witness(witness)),
+        Some(prove_request::WitnessInput::WitnessResult(result)) => {
+            Some(WitnessInput::Result(result.into()))
+        }
+        Some(prove_request::WitnessInput::WitnessBytes(bytes)) => {
+            let options = options.get_or_insert_with(RequestOptions::default);
+            options.witness_encoding = match options.witness_encoding {
+                Some(WitnessEncoding::Zstd) => Some(WitnessEncoding::Zstd),
+                Some(WitnessEncoding::Gzip) => Some(WitnessEncoding::Gzip),
+                _ => Some(WitnessEncoding::Base64),
+            };
+            Some(WitnessInput::Witness(BASE64.encode(bytes)))
+        }
+        None => None,
+    };
+    (witness, options)
+}
+
+/// Maps the error of a JSON-RPC method to the gRPC status, as the REST API does.
+pub fn to_status(error: &JsonError) -> Status {
+    let code = match status_of(error) {
+        StatusCode::BAD_REQUEST => Code::InvalidArgument,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::BAD_GATEWAY => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    Status::new(code, error.message.clone())
+}
+
+/// Rejects invalid hashes before a method is called, as their errors carry no code for
+/// `to_status`.
+fn check_hashes(l2_hash: &str, l1_head_hash: &str) -> Result<(), Status> {
+    match preprocessing(l2_hash, l1_head_hash) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Status::invalid_argument(e.to_string())),
+    }
+}
+
+// NOTE(Ethan): the handlers block on the SP1 network, so they do not run on the server.
+async fn run_blocking<T: Send + 'static>(
+    call: impl FnOnce() -> JsonResult<T> + Send + 'static,
+) -> Result<T, Status> {
+    match tokio::task::spawn_blocking(call).await {
+        Ok(result) => result.map_err(|e| to_status(&e)),
+        Err(e) => {
+            tracing::error!("A gRPC handler panicked: {:?}", e);
+            Err(Status::internal("The handler panicked"))
+        }
+    }
+}
+
+/// Runs a call of `method` and records it in the metrics of the methods.
+async fn call_blocking<T: Send + 'static>(
+    method: &str,
+    call: impl FnOnce() -> JsonResult<T> + Send + 'static,
+) -> Result<T, Status> {
+    let started = Instant::now();
+    let result = run_blocking(call).await;
+    metrics::observe_rpc(method, started, result.is_ok());
+    result
+}
+
+/// The gRPC interface, which calls the JSON-RPC methods of the same name.
+pub struct GrpcService {
+    rpc_impl: RpcImpl,
+    watch_interval: Duration,
+}
+
+impl GrpcService {
+    pub fn new(rpc_impl: RpcImpl, watch_interval: Duration) -> Self {
+        Self { rpc_impl, watch_interval }
+    }
+}
+
+type StatusStream = Pin<Box<dyn Stream<Item = Result<StatusResponse, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl ProverProxy for GrpcService {
+    async fn spec(&self, _: Request<SpecRequest>) -> Result<Response<SpecResponse>, Status> {
+        let started = Instant::now();
+        let spec = self.rpc_impl.spec().map_err(|e| to_status(&e));
+        metrics::observe_rpc("spec", started, spec.is_ok());
+        let spec = spec?;
+        Ok(Response::new(spec.into()))
+    }
+
+    async fn request_prove(
+        &self,
+        request: Request<ProveRequest>,
+    ) -> Result<Response<RequestResponse>, Status> {
+        let caller = request
+            .metadata()
+            .get(CALLER_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let request = request.into_inner();
+        tracing::info!(
+            "Received gRPC request prove: {:?}, {:?}",
+            request.l2_hash,
+            request.l1_head_hash
+        );
+        check_hashes(&request.l2_hash, &request.l1_head_hash)?;
+
+        let (witness, options) = prove_input(request.witness_input, request.options);
+        let rpc_impl = self.rpc_impl.clone();
+        let status = call_blocking("requestProve", move || {
+            rpc_impl.request_prove(
+                CallerMeta { caller, session: None },
+                request.l2_hash,
+                request.l1_head_hash,
+                witness,
+                options,
+            )
+        })
+        .await?;
+        Ok(Response::new(RequestResponse { status: RequestStatus::from(status).into() }))
+    }
+
+    async fn get_status(
+        &self,
+        request: Request<ProofKey>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let ProofKey { l2_hash, l1_head_hash } = request.into_inner();
+        check_hashes(&l2_hash, &l1_head_hash)?;
+        let rpc_impl = self.rpc_impl.clone();
+        let status =
+            call_blocking("getStatus", move || rpc_impl.get_status(l2_hash, l1_head_hash)).await?;
+        Ok(Response::new(status.into()))
+    }
+
+    async fn get_proof(
+        &self,
+        request: Request<ProofKey>,
+    ) -> Result<Response<ProofResponse>, Status> {
+        let ProofKey { l2_hash, l1_head_hash } = request.into_inner();
+        check_hashes(&l2_hash, &l1_head_hash)?;
+        let rpc_impl = self.rpc_impl.clone();
+        let proof =
+            call_blocking("getProof", move || rpc_impl.get_proof(l2_hash, l1_head_hash)).await?;
+        Ok(Response::new(proof.into()))
+    }
+
+    async fn cancel_request(
+        &self,
+        request: Request<ProofKey>,
+    ) -> Result<Response<RequestResponse>, Status> {
+        let ProofKey { l2_hash, l1_head_hash } = request.into_inner();
+        check_hashes(&l2_hash, &l1_head_hash)?;
+        let rpc_impl = self.rpc_impl.clone();
+        let status =
+            call_blocking("cancelRequest", move || rpc_impl.cancel_request(l2_hash, l1_head_hash))
+                .await?;
+        Ok(Response::new(RequestResponse { status: RequestStatus::from(status).into() }))
+    }
+
+    type WatchStatusStream = StatusStream;
+
+    async fn watch_status(
+        &self,
+        request: Request<ProofKey>,
+    ) -> Result<Response<Self::WatchStatusStream>, Status> {
+        let ProofKey { l2_hash, l1_head_hash } = request.into_inner();
+        check_hashes(&l2_hash, &l1_head_hash)?;
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+        let rpc_impl = self.rpc_impl.clone();
+        let interval = self.watch_interval;
+
+        tokio::spawn(async move {
+            let mut last_status = None;
+            loop {
+                // NOTE(Ethan): a watcher which is gone while the status is unchanged is never sent
+                // to, so the channel is checked for it before every poll.
+                if sender.is_closed() {
+                    tracing::info!("The gRPC watcher is gone");
+                    return;
+                }
+                // NOTE(Ethan): only the status is queried from the SP1 network, so the proof is
+                // downloaded once, when the request completes.
+                let (rpc_impl, l2_hash, l1_head_hash) =
+                    (rpc_impl.clone(), l2_hash.clone(), l1_head_hash.clone());
+                let result =
+                    run_blocking(move || rpc_impl.refresh_status(l2_hash, l1_head_hash)).await;
+                let status = match result {
+                    Ok(status) => status,
+                    Err(e) => {
+                        let _ = sender.send(Err(e)).await;
+                        return;
+                    }
+                };
+
+                let is_final = status.status.as_ref().map_or(false, RequestResult::is_final);
+                if status.status != last_status {
+                    last_status = status.status.clone();
+                    if sender.send(Ok(status.into())).await.is_err() {
+                        tracing::info!("The gRPC watcher is gone");
+                        return;
+                    }
+                }
+                if is_final {
+                    return;
+                }
+                tokio::select! {
+                    _ = sender.closed() => {
+                        tracing::info!("The gRPC watcher is gone");
+                        return;
+                    }
+                    _ = tokio::time::sleep(interval) => {}
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
+
+/// Serves the gRPC interface at `endpoint` in a background thread. `WatchStatus` queries the
+/// request every `watch_interval`.
+pub fn spawn_grpc_server(
+    rpc_impl: RpcImpl,
+    endpoint: SocketAddr,
+    watch_interval: Duration,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to create a new runtime");
+        let service = ProverProxyServer::new(GrpcService::new(rpc_impl, watch_interval))
+            .max_decoding_message_size(MAX_DECOMPRESSED_SIZE);
+        let server = Server::builder().add_service(service).serve(endpoint);
+        if let Err(e) = runtime.block_on(server) {
+            tracing::error!("The gRPC server stopped: {:?}", e);
+        }
+    })
+}
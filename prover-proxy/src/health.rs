@@ -0,0 +1,178 @@
+use anyhow::Result;
+use jsonrpc_http_server::hyper::{Body, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    interface::RpcImpl, proof_db::SCHEMA_VERSION, rest::json_response, utils::block_on,
+    VERIFICATION_KEY_HASH,
+};
+
+pub const HEALTHZ_PATH: &str = "/healthz";
+pub const READYZ_PATH: &str = "/readyz";
+
+static NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The outcome of a single check of `/healthz` or `/readyz`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CheckResult {
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl CheckResult {
+    pub fn pass(detail: impl Into<String>) -> Self {
+        Self { ok: true, detail: detail.into() }
+    }
+
+    pub fn fail(detail: impl Into<String>) -> Self {
+        Self { ok: false, detail: detail.into() }
+    }
+}
+
+impl From<Result<String>> for CheckResult {
+    fn from(result: Result<String>) -> Self {
+        match result {
+            Ok(detail) => Self::pass(detail),
+            Err(e) => Self::fail(e.to_string()),
+        }
+    }
+}
+
+/// The body of `/healthz` and `/readyz`, which is ok only if every check is.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct HealthReport {
+    pub ok: bool,
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+impl HealthReport {
+    pub fn new(checks: BTreeMap<String, CheckResult>) -> Self {
+        Self { ok: checks.values().all(|check| check.ok), checks }
+    }
+}
+
+/// The background threads of the proxy, which loop until the process exits. One which has
+/// stopped, e.g. by a panic, makes the proxy unhealthy.
+#[derive(Default)]
+pub struct Workers {
+    handles: Mutex<Vec<(String, thread::JoinHandle<()>)>>,
+}
+
+impl Workers {
+    pub fn register(&self, name: &str, handle: thread::JoinHandle<()>) {
+        self.handles.lock().unwrap().push((name.to_string(), handle));
+    }
+
+    /// Returns the names of the workers which have stopped.
+    pub fn stopped(&self) -> Vec<String> {
+        let handles = self.handles.lock().unwrap();
+        handles
+            .iter()
+            .filter(|(_, handle)| handle.is_finished())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn check(&self) -> CheckResult {
+        let stopped = self.stopped();
+        if stopped.is_empty() {
+            CheckResult::pass(format!("{} running", self.handles.lock().unwrap().len()))
+        } else {
+            CheckResult::fail(format!("Stopped: {}", stopped.join(", ")))
+        }
+    }
+}
+
+/// Backs `/healthz`, which checks what the proxy needs to keep running, and `/readyz`, which
+/// also checks what it needs to serve requests.
+pub struct HealthCheck {
+    rpc_impl: RpcImpl,
+    pub workers: Workers,
+}
+
+impl HealthCheck {
+    pub fn new(rpc_impl: RpcImpl) -> Self {
+        Self { rpc_impl, workers: Workers::default() }
+    }
+
+    pub fn is_health_path(path: &str) -> bool {
+        path == HEALTHZ_PATH || path == READYZ_PATH
+    }
+
+    pub fn liveness(&self) -> HealthReport {
+        let mut checks = BTreeMap::new();
+        checks.insert("store".to_string(), self.check_store());
+        checks.insert("workers".to_string(), self.workers.check());
+        HealthReport::new(checks)
+    }
+
+    pub fn readiness(&self) -> HealthReport {
+        let (network, program) = self.check_network();
+        let mut checks = BTreeMap::new();
+        checks.insert("store".to_string(), self.check_store());
+        checks.insert("network".to_string(), network);
+        checks.insert("program".to_string(), program);
+        checks.insert("workers".to_string(), self.workers.check());
+        HealthReport::new(checks)
+    }
+
+    fn check_store(&self) -> CheckResult {
+        let proof_db = &self.rpc_impl.proof_db;
+        proof_db.check_readable().map(|_| format!("v{}", SCHEMA_VERSION)).into()
+    }
+
+    /// Looks the program up on the SP1 network, which tells both whether the network is
+    /// reachable and whether the program is registered to it.
+    fn check_network(&self) -> (CheckResult, CheckResult) {
+        let client = self.rpc_impl.client.clone();
+        let program = block_on(async move {
+            tokio::time::timeout(NETWORK_TIMEOUT, client.get_program(*VERIFICATION_KEY_HASH)).await
+        });
+        let program = match program {
+            Ok(Ok(program)) => program,
+            Ok(Err(e)) => {
+                let network = CheckResult::fail(format!("Failed to reach the SP1 network: {}", e));
+                return (network, CheckResult::fail("Unknown"));
+            }
+            Err(_) => {
+                let network = CheckResult::fail(format!("No answer in {:?}", NETWORK_TIMEOUT));
+                return (network, CheckResult::fail("Unknown"));
+            }
+        };
+
+        if program.is_some() {
+            self.rpc_impl.set_program_registered();
+        }
+        let program = if self.rpc_impl.is_program_registered() {
+            CheckResult::pass(format!("{} is registered", *VERIFICATION_KEY_HASH))
+        } else {
+            CheckResult::fail(format!("{} is not registered", *VERIFICATION_KEY_HASH))
+        };
+        (CheckResult::pass("Reachable"), program)
+    }
+
+    /// Answers `/healthz` or `/readyz` with the report, and 503 if it is not ok.
+    pub async fn handle(self: Arc<Self>, path: String) -> Response<Body> {
+        // NOTE(Ethan): the checks block on the store and the SP1 network, so they do not run on
+        // the server.
+        let report = tokio::task::spawn_blocking(move || match path.as_str() {
+            READYZ_PATH => self.readiness(),
+            _ => self.liveness(),
+        })
+        .await;
+        let report = report.unwrap_or_else(|e| {
+            let mut checks = BTreeMap::new();
+            checks.insert("health".to_string(), CheckResult::fail(format!("Panicked: {}", e)));
+            HealthReport::new(checks)
+        });
+
+        let status = if report.ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+        json_response(status, serde_json::to_vec(&report).expect("reports are serializable"))
+    }
+}
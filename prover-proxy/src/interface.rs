@@ -1,45 +1,355 @@
-use jsonrpc_core::Result as JsonResult;
+use alloy_primitives::B256;
+use anyhow::Result;
+use jsonrpc_core::{
+    futures::future::Either, Call, ErrorCode as JsonErrorCode, FutureOutput, FutureResponse,
+    Middleware, Output, Result as JsonResult, Value,
+};
 use jsonrpc_derive::rpc;
+use jsonrpc_http_server::hyper;
+use jsonrpc_pubsub::{typed::Subscriber, PubSubMetadata, Session, SubscriptionId};
 use kroma_zkvm_common::types::preprocessing;
 use sp1_sdk::network::NetworkClient;
-use std::sync::{Arc, RwLock};
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
 
 use crate::errors::ProverError;
-use crate::proof_db::ProofDB;
-use crate::types::{ProofResult, RequestResult, SpecResult};
+use crate::metrics;
+use crate::proof_db::{PendingCallback, ProofDB};
+use crate::queue::{QueuedRequest, SubmissionQueue};
+use crate::subscriptions::ProofSubscriptions;
+use crate::types::{
+    BatchItemResult, ListRequestsFilter, ListRequestsResult, ProofRequestItem, ProofResult,
+    ProveRequestItem, RequestMetadata, RequestOptions, RequestProofResult, RequestResult,
+    SpecResult, StatusResult, UploadStatus, WitnessEncoding, WitnessInput, WitnessResult,
+    MAX_BATCH_SIZE, MAX_LABELS, MAX_LABEL_LEN,
+};
+use crate::uploads::UploadStore;
+use crate::utils::{now_secs, run_concurrently, KeyedLock};
+use crate::witness_generator::WitnessGenerator;
 
-use crate::{DEFAULT_NETWORK_RPC_URL, DEFAULT_PROOF_STORE_PATH};
+use crate::{DEFAULT_NETWORK_RPC_URL, DEFAULT_PROOF_STORE_PATH, DEFAULT_UPLOAD_PATH};
+
+/// The OpenRPC document of `Rpc`, returned by `rpc.discover`. Its version is bumped whenever a
+/// method or a type of it changes.
+pub const OPENRPC_DOCUMENT: &str = include_str!("openrpc.json");
+
+/// The header which identifies the caller of a request, recorded in the request metadata.
+pub const CALLER_HEADER: &str = "x-caller";
+
+/// Metadata of a JSON-RPC call, extracted from its HTTP request or WebSocket connection.
+#[derive(Clone, Default)]
+pub struct CallerMeta {
+    pub caller: Option<String>,
+    /// The WebSocket session which receives the notifications of subscriptions.
+    pub session: Option<Arc<Session>>,
+}
+
+impl jsonrpc_core::Metadata for CallerMeta {}
+
+impl PubSubMetadata for CallerMeta {
+    fn session(&self) -> Option<Arc<Session>> {
+        self.session.clone()
+    }
+}
+
+impl CallerMeta {
+    pub fn from_request(request: &hyper::Request<hyper::Body>) -> Self {
+        Self::from_headers(request.headers())
+    }
+
+    pub fn from_headers(headers: &hyper::HeaderMap) -> Self {
+        let caller = headers
+            .get(CALLER_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        Self { caller, session: None }
+    }
+
+    pub fn from_ws_context(context: &jsonrpc_ws_server::RequestContext) -> Self {
+        Self { caller: None, session: Some(Arc::new(Session::new(context.sender()))) }
+    }
+}
+
+/// Records every JSON-RPC call in `metrics::RPC_CALLS` and `metrics::RPC_DURATION`.
+#[derive(Default)]
+pub struct RpcMetrics;
+
+impl Middleware<CallerMeta> for RpcMetrics {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_call<F, X>(&self, call: Call, meta: CallerMeta, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, CallerMeta) -> X + Send + Sync,
+        X: std::future::Future<Output = Option<Output>> + Send + 'static,
+    {
+        let method = match &call {
+            Call::MethodCall(call) => call.method.clone(),
+            Call::Notification(notification) => notification.method.clone(),
+            Call::Invalid { .. } => return Either::Right(next(call, meta)),
+        };
+        let started = Instant::now();
+        let output = next(call, meta);
+        Either::Left(Box::pin(async move {
+            let output = output.await;
+            match &output {
+                // NOTE(Ethan): unknown methods are not labelled by their name, which the caller
+                // chooses.
+                Some(Output::Failure(failure))
+                    if failure.error.code == JsonErrorCode::MethodNotFound =>
+                {
+                    metrics::observe_rpc("unknown", started, false)
+                }
+                Some(Output::Failure(_)) => metrics::observe_rpc(&method, started, false),
+                _ => metrics::observe_rpc(&method, started, true),
+            }
+            output
+        }))
+    }
+}
 
 #[rpc]
 pub trait Rpc {
+    type Metadata;
+
     #[rpc(name = "spec")]
     fn spec(&self) -> JsonResult<SpecResult>;
 
-    #[rpc(name = "requestProve")]
+    #[rpc(meta, name = "requestProve")]
     fn request_prove(
         &self,
+        meta: Self::Metadata,
         l2_hash: String,
         l1_head_hash: String,
-        witness: String,
+        witness: Option<WitnessInput>,
+        options: Option<RequestOptions>,
+    ) -> JsonResult<RequestResult>;
+
+    #[rpc(meta, name = "requestProveBatch")]
+    fn request_prove_batch(
+        &self,
+        meta: Self::Metadata,
+        items: Vec<ProveRequestItem>,
+    ) -> JsonResult<Vec<BatchItemResult<RequestResult>>>;
+
+    /// Starts a chunked upload of the witness of `requestProve`.
+    #[rpc(name = "beginUpload")]
+    fn begin_upload(
+        &self,
+        l2_hash: String,
+        l1_head_hash: String,
+        options: Option<RequestOptions>,
+    ) -> JsonResult<UploadStatus>;
+
+    /// Appends a chunk of the witness string at `offset`.
+    #[rpc(name = "appendUpload")]
+    fn append_upload(
+        &self,
+        session_id: String,
+        offset: u64,
+        chunk: String,
+    ) -> JsonResult<UploadStatus>;
+
+    #[rpc(name = "getUpload")]
+    fn get_upload(&self, session_id: String) -> JsonResult<UploadStatus>;
+
+    /// Checks the uploaded witness against its keccak256 `hash` and requests the proof.
+    #[rpc(meta, name = "commitUpload")]
+    fn commit_upload(
+        &self,
+        meta: Self::Metadata,
+        session_id: String,
+        hash: String,
     ) -> JsonResult<RequestResult>;
 
     #[rpc(name = "getProof")]
     fn get_proof(&self, l2_hash: String, l1_head_hash: String) -> JsonResult<ProofResult>;
+
+    #[rpc(name = "getProofs")]
+    fn get_proofs(
+        &self,
+        items: Vec<ProofRequestItem>,
+    ) -> JsonResult<Vec<BatchItemResult<ProofResult>>>;
+
+    #[rpc(name = "getProofByRequestId")]
+    fn get_proof_by_request_id(&self, request_id: String) -> JsonResult<RequestProofResult>;
+
+    /// Pushes the `ProofResult` of a request whenever its status changes, ending with its final
+    /// result. Only available over WebSocket.
+    #[pubsub(subscription = "proof", subscribe, name = "subscribeProof")]
+    fn subscribe_proof(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<ProofResult>,
+        l2_hash: String,
+        l1_head_hash: String,
+    );
+
+    #[pubsub(subscription = "proof", unsubscribe, name = "unsubscribeProof")]
+    fn unsubscribe_proof(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> JsonResult<bool>;
+
+    #[rpc(name = "getStatus")]
+    fn get_status(&self, l2_hash: String, l1_head_hash: String) -> JsonResult<StatusResult>;
+
+    #[rpc(name = "cancelRequest")]
+    fn cancel_request(&self, l2_hash: String, l1_head_hash: String) -> JsonResult<RequestResult>;
+
+    #[rpc(name = "listRequests")]
+    fn list_requests(&self, filter: Option<ListRequestsFilter>) -> JsonResult<ListRequestsResult>;
+
+    /// Returns the OpenRPC document of this interface.
+    #[rpc(name = "rpc.discover")]
+    fn discover(&self) -> JsonResult<Value>;
 }
 
 #[derive(Clone)]
 pub struct RpcImpl {
-    task_lock: Arc<RwLock<()>>,
-    proof_db: Arc<ProofDB>,
+    task_lock: Arc<KeyedLock>,
+    pub proof_db: Arc<ProofDB>,
     pub client: Arc<NetworkClient>,
+    pub subscriptions: Arc<ProofSubscriptions>,
+    pub uploads: Arc<UploadStore>,
+    pub queue: Arc<SubmissionQueue>,
+    witness_generator: Option<Arc<WitnessGenerator>>,
+    callbacks_enabled: bool,
+    program_registered: Arc<AtomicBool>,
 }
 
 impl RpcImpl {
     pub fn new(store_path: &str, sp1_private_key: &str, network_rpc_url: &str) -> Self {
+        Self::with_proof_db(Arc::new(ProofDB::new(store_path)), sp1_private_key, network_rpc_url)
+    }
+
+    pub fn with_proof_db(
+        proof_db: Arc<ProofDB>,
+        sp1_private_key: &str,
+        network_rpc_url: &str,
+    ) -> Self {
         RpcImpl {
-            task_lock: Arc::new(RwLock::new(())),
-            proof_db: Arc::new(ProofDB::new(store_path)),
+            task_lock: Arc::new(KeyedLock::default()),
+            proof_db,
             client: Arc::new(NetworkClient::new(sp1_private_key, network_rpc_url)),
+            subscriptions: Arc::new(ProofSubscriptions::default()),
+            uploads: Arc::new(UploadStore::new(DEFAULT_UPLOAD_PATH, None)),
+            queue: Arc::new(SubmissionQueue::default()),
+            witness_generator: None,
+            callbacks_enabled: false,
+            program_registered: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_uploads(mut self, uploads: UploadStore) -> Self {
+        self.uploads = Arc::new(uploads);
+        self
+    }
+
+    /// Lets `requestProve` be given no witness, which is then fetched from the witness generator.
+    pub fn with_witness_generator(mut self, witness_generator: Option<WitnessGenerator>) -> Self {
+        self.witness_generator = witness_generator.map(Arc::new);
+        self
+    }
+
+    /// Accepts `callback_url`s, which must be delivered by a `CallbackDispatcher`.
+    pub fn with_callbacks(mut self, enabled: bool) -> Self {
+        self.callbacks_enabled = enabled;
+        self
+    }
+
+    /// Reports in `spec` that the program is registered to the SP1 network.
+    pub fn set_program_registered(&self) {
+        self.program_registered.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_program_registered(&self) -> bool {
+        self.program_registered.load(Ordering::Relaxed)
+    }
+
+    fn check_options(&self, options: &RequestOptions) -> JsonResult<()> {
+        if let Some(deadline) = options.deadline {
+            if deadline <= now_secs() {
+                tracing::error!("Invalid parameters - \"deadline\": {:?}", deadline);
+                return Err(jsonrpc_core::Error::invalid_params("The deadline has passed"));
+            }
+        }
+        if options.labels.len() > MAX_LABELS {
+            tracing::error!("Invalid parameters - {} labels", options.labels.len());
+            return Err(jsonrpc_core::Error::invalid_params(format!(
+                "A request can have at most {} labels",
+                MAX_LABELS
+            )));
+        }
+        let values = options.labels.iter().flat_map(|(name, value)| [name, value]);
+        if values.chain(&options.idempotency_key).any(|value| value.len() > MAX_LABEL_LEN) {
+            tracing::error!("Invalid parameters - a label or the idempotency key is too long");
+            return Err(jsonrpc_core::Error::invalid_params(format!(
+                "Labels and idempotency keys are limited to {} bytes",
+                MAX_LABEL_LEN
+            )));
+        }
+        if let Some(url) = &options.callback_url {
+            if !self.callbacks_enabled {
+                tracing::error!("Invalid parameters - callbacks are disabled: {:?}", url);
+                return Err(jsonrpc_core::Error::invalid_params("Callbacks are not enabled"));
+            }
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                tracing::error!("Invalid parameters - \"callback_url\": {:?}", url);
+                return Err(jsonrpc_core::Error::invalid_params("Invalid callback url"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the idempotency key of an accepted request, so that its retries are answered like
+    /// it.
+    fn record_idempotency_key(
+        &self,
+        options: &RequestOptions,
+        l2_hash: &B256,
+        l1_head_hash: &B256,
+    ) -> JsonResult<()> {
+        if let Some(key) = &options.idempotency_key {
+            self.proof_db.set_idempotency_key(key, l2_hash, l1_head_hash).map_err(|e| {
+                tracing::error!("Failed to record the idempotency key: {:?}", e);
+                ProverError::store_error(e.to_string()).to_json_error_with_code()
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Registers the callback of an accepted request. A request which is not submitted yet has no
+    /// `request_id`, which is attached once it is.
+    fn register_callback(
+        &self,
+        l2_hash: &B256,
+        l1_head_hash: &B256,
+        request_id: Option<B256>,
+        options: &RequestOptions,
+    ) -> JsonResult<()> {
+        if let Some(url) = &options.callback_url {
+            let callback = PendingCallback::new(l2_hash, l1_head_hash, request_id, url.clone());
+            self.proof_db.add_callback(callback).map_err(|e| {
+                tracing::error!("Failed to register the callback: {:?}", e);
+                jsonrpc_core::Error::internal_error()
+            })?;
+            tracing::info!("Registered a callback of {:?}, {:?}", l2_hash, l1_head_hash);
+        }
+        Ok(())
+    }
+
+    /// Reports a request which failed before it reached the SP1 network to its callbacks.
+    fn fail_callbacks(&self, l2_hash: &B256, l1_head_hash: &B256) {
+        if let Err(e) = self.proof_db.fail_callbacks(l2_hash, l1_head_hash) {
+            tracing::error!("Failed to report the failure to the callbacks: {:?}", e);
         }
     }
 }
@@ -52,19 +362,192 @@ impl Default for RpcImpl {
     }
 }
 
+impl RpcImpl {
+    /// Starts `workers` threads which submit the queued requests to the SP1 Network Prover.
+    pub fn spawn_submitters(&self, workers: usize) -> Vec<std::thread::JoinHandle<()>> {
+        (0..workers)
+            .map(|_| {
+                let rpc_impl = self.clone();
+                std::thread::spawn(move || rpc_impl.run_submitter())
+            })
+            .collect()
+    }
+
+    /// Returns the status of the request of the hashes as `getStatus` does, after querying the
+    /// SP1 network for it while it is processing. The proof is only downloaded once it completes.
+    pub fn refresh_status(
+        &self,
+        l2_hash: String,
+        l1_head_hash: String,
+    ) -> JsonResult<StatusResult> {
+        let status = self.get_status(l2_hash.clone(), l1_head_hash.clone())?;
+        let request_id = match (&status.status, status.request_id) {
+            (Some(RequestResult::Processing), Some(request_id)) => request_id,
+            _ => return Ok(status),
+        };
+
+        // NOTE(Ethan): `getStatus` above has checked the hashes.
+        let (_, _, user_req_id) = preprocessing(&l2_hash, &l1_head_hash).unwrap();
+        {
+            let _guard = self.task_lock.lock(&user_req_id);
+            crate::utils::get_status_by_remote_id(&self.client, &self.proof_db, request_id);
+        }
+        self.get_status(l2_hash, l1_head_hash)
+    }
+
+    fn run_submitter(&self) {
+        loop {
+            let request = self.queue.pop();
+            let _guard = self.task_lock.lock(&request.user_req_id);
+            if self.queue.status(&request.user_req_id) == Some(RequestResult::Cancelled) {
+                tracing::info!("Dropped a cancelled request: {:?}", request.user_req_id);
+                continue;
+            }
+            let (user_req_id, l2_hash, l1_head_hash) =
+                (request.user_req_id.clone(), request.l2_hash, request.l1_head_hash);
+            match self.submit(request) {
+                Ok(_) => self.queue.set_status(&user_req_id, None),
+                Err(e) => {
+                    tracing::error!("Failed to submit the request: {:?}: {:?}", user_req_id, e);
+                    self.queue.set_status(&user_req_id, Some(RequestResult::Failed));
+                    self.fail_callbacks(&l2_hash, &l1_head_hash);
+                }
+            }
+        }
+    }
+
+    /// Sends the witness to the SP1 Network Prover and stores the request.
+    fn submit(&self, request: QueuedRequest) -> Result<B256> {
+        let QueuedRequest {
+            user_req_id, l2_hash, l1_head_hash, caller, witness_buf, options, ..
+        } = request;
+
+        // The strategy and the timeout are chosen to meet the deadline, if there is one.
+        let estimated_proving_secs = self.proof_db.estimated_proving_secs();
+        let (strategy, timeout_secs) =
+            crate::utils::fulfillment_plan(options.deadline, estimated_proving_secs);
+        let at_risk = options
+            .deadline
+            .map_or(false, |deadline| now_secs() + estimated_proving_secs > deadline);
+        if at_risk {
+            tracing::warn!(
+                "The request is unlikely to meet its deadline: {:?}, {:?}",
+                user_req_id,
+                options.deadline
+            );
+        }
+
+        let net_req_id =
+            crate::utils::request_prove_to_sp1(&self.client, witness_buf, strategy, timeout_secs)?;
+        tracing::info!("Sent request to SP1 network: {:?}, {:?}", user_req_id, net_req_id);
+
+        // Keep the previous request in the history, in case of a forced request.
+        if options.force {
+            let discarded = self.proof_db.discard_request(&l2_hash, &l1_head_hash)?;
+            tracing::info!("Discarded the previous request: {:?}, {:?}", user_req_id, discarded);
+        }
+
+        // Store the `net_req_id` to the database.
+        self.proof_db.set_request_id(&l2_hash, &l1_head_hash, &net_req_id)?;
+        let mut metadata = RequestMetadata::new(net_req_id, l2_hash, l1_head_hash, caller);
+        metadata.priority = options.priority.unwrap_or_default();
+        metadata.deadline = options.deadline;
+        metadata.at_risk = at_risk;
+        metadata.idempotency_key = options.idempotency_key.clone();
+        metadata.labels = options.labels.clone();
+        self.proof_db.set_request_metadata(&metadata)?;
+        tracing::info!("Stored \"net_req_id\" to db: {:?}, {:?}", user_req_id, net_req_id);
+        self.proof_db.attach_callbacks(&l2_hash, &l1_head_hash, &net_req_id)?;
+
+        Ok(net_req_id)
+    }
+
+    /// Fetches the witness from the witness generator in the background, and queues the request
+    /// once it is generated. The request is `Processing` meanwhile, and `Failed` if the fetch
+    /// fails.
+    fn fetch_witness(
+        &self,
+        meta: CallerMeta,
+        l2_hash: B256,
+        l1_head_hash: B256,
+        user_req_id: String,
+        options: RequestOptions,
+    ) -> JsonResult<RequestResult> {
+        let witness_generator = self.witness_generator.clone().ok_or_else(|| {
+            tracing::error!("Invalid parameters - no witness is given: {:?}", user_req_id);
+            jsonrpc_core::Error::invalid_params("A witness must be given")
+        })?;
+        // NOTE(Ethan): the callback is registered before the fetch starts, so that a failed fetch
+        // finds it.
+        self.register_callback(&l2_hash, &l1_head_hash, None, &options)?;
+        self.queue.set_status(&user_req_id, Some(RequestResult::Processing));
+        tracing::info!("Fetching the witness from the witness generator: {:?}", user_req_id);
+
+        let rpc_impl = self.clone();
+        std::thread::spawn(move || {
+            let result =
+                witness_generator.fetch(&l2_hash, &l1_head_hash).and_then(|witness_result| {
+                    witness_result.check()?;
+                    witness_result.get_witness_buf_with_encoding(WitnessEncoding::Hex)
+                });
+            let _guard = rpc_impl.task_lock.lock(&user_req_id);
+            if rpc_impl.queue.status(&user_req_id) == Some(RequestResult::Cancelled) {
+                tracing::info!("Dropped the witness of a cancelled request: {:?}", user_req_id);
+                return;
+            }
+            match result {
+                Ok(witness_buf) => rpc_impl.queue.push(QueuedRequest::new(
+                    user_req_id,
+                    l2_hash,
+                    l1_head_hash,
+                    meta.caller,
+                    witness_buf,
+                    options,
+                )),
+                Err(e) => {
+                    tracing::error!("Failed to fetch the witness: {:?}: {:?}", user_req_id, e);
+                    rpc_impl.queue.set_status(&user_req_id, Some(RequestResult::Failed));
+                    rpc_impl.fail_callbacks(&l2_hash, &l1_head_hash);
+                }
+            }
+        });
+
+        Ok(RequestResult::Processing)
+    }
+}
+
+fn check_batch_size(len: usize) -> JsonResult<()> {
+    if len > MAX_BATCH_SIZE {
+        tracing::error!("Invalid parameters - batch of {} items", len);
+        return Err(jsonrpc_core::Error::invalid_params(format!(
+            "A batch can have at most {} items",
+            MAX_BATCH_SIZE
+        )));
+    }
+    Ok(())
+}
+
 impl Rpc for RpcImpl {
+    type Metadata = CallerMeta;
+
     fn spec(&self) -> JsonResult<SpecResult> {
-        let spec = SpecResult::default();
+        let mut spec = SpecResult::default();
+        spec.queue_depth = self.queue.len() as u64;
+        spec.program_registered = self.is_program_registered();
         tracing::info!("Received sepc: {:?}", spec);
         Ok(spec)
     }
 
     fn request_prove(
         &self,
+        meta: CallerMeta,
         l2_hash: String,
         l1_head_hash: String,
-        witness: String,
+        witness: Option<WitnessInput>,
+        options: Option<RequestOptions>,
     ) -> JsonResult<RequestResult> {
+        let mut options = options.unwrap_or_default();
+        self.check_options(&options)?;
         let (l2_hash, l1_head_hash, user_req_id) =
             preprocessing(&l2_hash, &l1_head_hash).map_err(|e| {
                 tracing::error!(
@@ -77,35 +560,187 @@ impl Rpc for RpcImpl {
         tracing::info!("Received request - \"user_req_id\": {:?}", user_req_id);
 
         // Check a status of the request.
-        let _guard = self.task_lock.write().unwrap();
+        let _guard = self.task_lock.lock(&user_req_id);
+        if let Some(key) = &options.idempotency_key {
+            match self.proof_db.get_idempotency_key(key) {
+                Some(hashes) if hashes != (l2_hash, l1_head_hash) => {
+                    tracing::error!("Invalid parameters - \"idempotency_key\": {:?}", key);
+                    return Err(jsonrpc_core::Error::invalid_params(
+                        "The idempotency key was used with other hashes",
+                    ));
+                }
+                // NOTE(Ethan): a retry of a forced request must not discard what the first call
+                // submitted.
+                Some(_) => {
+                    tracing::info!("Retried with the idempotency key: {:?}", key);
+                    options.force = false;
+                }
+                None => {}
+            }
+        }
+        if self.queue.status(&user_req_id) == Some(RequestResult::Processing) {
+            tracing::info!("The request is not submitted yet: {:?}", user_req_id);
+            self.register_callback(&l2_hash, &l1_head_hash, None, &options)?;
+            return Ok(RequestResult::Processing);
+        }
         let req_status = crate::utils::get_status_by_local_id(
             &self.client,
             &self.proof_db,
             &l2_hash,
             &l1_head_hash,
         );
-        
+
         tracing::info!("Check the status of the request: {:?}, {:?}", user_req_id, req_status);
-        // Return the status in case of `Processing` or `Completed`.
-        if req_status == RequestResult::Processing || req_status == RequestResult::Completed {
+        // Return the status in case of `Processing` or `Completed`, unless it is forced.
+        let requested =
+            req_status == RequestResult::Processing || req_status == RequestResult::Completed;
+        if requested && !options.force {
+            if let Some(request_id) = self.proof_db.get_request_id(&l2_hash, &l1_head_hash) {
+                self.register_callback(&l2_hash, &l1_head_hash, Some(request_id), &options)?;
+            }
+            self.record_idempotency_key(&options, &l2_hash, &l1_head_hash)?;
             return Ok(req_status);
         }
 
-        // Send a request to the SP1 Network Prover only if the status is `None` or `Failed`.
-        let net_req_id =
-            crate::utils::request_prove_to_sp1(&self.client, witness).map_err(|e| {
-                tracing::error!("Failed to send request to SP1 network: {:?}", e);
-                ProverError::sp1_network_error(e.to_string()).to_json_error()
-            })?;
-        tracing::info!("Sent request to SP1 network: {:?}, {:?}", user_req_id, net_req_id);
-
-        // Store the `net_req_id` to the database.
-        self.proof_db.set_request_id(&l2_hash, &l1_head_hash, &net_req_id).unwrap();
-        tracing::info!("Stored \"net_req_id\" to db: {:?}, {:?}", user_req_id, net_req_id);
+        // Send a request to the SP1 Network Prover only if the status is `None`, `Failed` or
+        // `Cancelled`.
+        self.queue.set_status(&user_req_id, None);
+        let witness = match witness {
+            Some(witness) => witness.into_witness().map_err(|e| {
+                tracing::error!("Invalid witness: {:?}: {:?}", user_req_id, e);
+                ProverError::invalid_witness(e.to_string()).to_json_error_with_code()
+            })?,
+            None => {
+                self.record_idempotency_key(&options, &l2_hash, &l1_head_hash)?;
+                return self.fetch_witness(meta, l2_hash, l1_head_hash, user_req_id, options);
+            }
+        };
+        let encoding = options.witness_encoding.unwrap_or_default();
+        let witness_buf = WitnessResult::decode_witness_buf(&witness, encoding).map_err(|e| {
+            tracing::error!("Invalid witness - \"encoding\": {:?}: {:?}", encoding, e);
+            ProverError::invalid_witness(e.to_string()).to_json_error_with_code()
+        })?;
+        self.record_idempotency_key(&options, &l2_hash, &l1_head_hash)?;
+        self.register_callback(&l2_hash, &l1_head_hash, None, &options)?;
+        self.queue.push(QueuedRequest::new(
+            user_req_id,
+            l2_hash,
+            l1_head_hash,
+            meta.caller,
+            witness_buf,
+            options,
+        ));
 
         Ok(RequestResult::Processing)
     }
 
+    fn request_prove_batch(
+        &self,
+        meta: CallerMeta,
+        items: Vec<ProveRequestItem>,
+    ) -> JsonResult<Vec<BatchItemResult<RequestResult>>> {
+        check_batch_size(items.len())?;
+        tracing::info!("Received batch request - {} item(s)", items.len());
+
+        Ok(run_concurrently(items, |item| {
+            self.request_prove(
+                meta.clone(),
+                item.l2_hash,
+                item.l1_head_hash,
+                item.witness,
+                item.options,
+            )
+            .into()
+        }))
+    }
+
+    fn begin_upload(
+        &self,
+        l2_hash: String,
+        l1_head_hash: String,
+        options: Option<RequestOptions>,
+    ) -> JsonResult<UploadStatus> {
+        let (_, _, user_req_id) = preprocessing(&l2_hash, &l1_head_hash).map_err(|e| {
+            tracing::error!(
+                "Invalid parameters - \"l2_hash\": {:?}, \"l1_head_hash\": {:?}",
+                l2_hash,
+                l1_head_hash
+            );
+            ProverError::invalid_input_hash(e.to_string()).to_json_error()
+        })?;
+        self.check_options(&options.clone().unwrap_or_default())?;
+
+        let session = self.uploads.begin(l2_hash, l1_head_hash, options).map_err(|e| {
+            tracing::error!("Failed to begin an upload: {:?}", e);
+            jsonrpc_core::Error::internal_error()
+        })?;
+        tracing::info!(
+            "Began upload - \"user_req_id\": {:?}, {:?}",
+            user_req_id,
+            session.session_id
+        );
+
+        Ok(session.status())
+    }
+
+    fn append_upload(
+        &self,
+        session_id: String,
+        offset: u64,
+        chunk: String,
+    ) -> JsonResult<UploadStatus> {
+        let session = self.uploads.append(&session_id, offset, chunk.as_bytes()).map_err(|e| {
+            tracing::error!("Failed to append to the upload {:?}: {:?}", session_id, e);
+            jsonrpc_core::Error::invalid_params(e.to_string())
+        })?;
+        tracing::debug!("Appended upload: {:?}, {}", session_id, session.received);
+
+        Ok(session.status())
+    }
+
+    fn get_upload(&self, session_id: String) -> JsonResult<UploadStatus> {
+        match self.uploads.get(&session_id) {
+            Ok(Some(session)) => Ok(session.status()),
+            Ok(None) => Err(ProverError::request_not_found(session_id).to_json_error_with_code()),
+            Err(e) => Err(jsonrpc_core::Error::invalid_params(e.to_string())),
+        }
+    }
+
+    fn commit_upload(
+        &self,
+        meta: CallerMeta,
+        session_id: String,
+        hash: String,
+    ) -> JsonResult<RequestResult> {
+        let hash = B256::from_str(&hash).map_err(|e| {
+            tracing::error!("Invalid parameters - \"hash\": {:?}", hash);
+            ProverError::invalid_input_hash(e.to_string()).to_json_error()
+        })?;
+        if self.get_upload(session_id.clone()).is_err() {
+            return Err(ProverError::request_not_found(session_id).to_json_error_with_code());
+        }
+
+        let (session, witness) = self.uploads.assemble(&session_id, &hash).map_err(|e| {
+            tracing::error!("Failed to assemble the upload {:?}: {:?}", session_id, e);
+            ProverError::invalid_witness(e.to_string()).to_json_error_with_code()
+        })?;
+        let witness = String::from_utf8(witness)
+            .map_err(|e| ProverError::invalid_witness(e.to_string()).to_json_error_with_code())?;
+        tracing::info!("Committed upload: {:?}, {} bytes", session_id, session.received);
+
+        let result = self.request_prove(
+            meta,
+            session.l2_hash,
+            session.l1_head_hash,
+            Some(WitnessInput::Witness(witness)),
+            session.options,
+        );
+        if result.is_ok() {
+            self.uploads.remove(&session_id);
+        }
+        result
+    }
+
     fn get_proof(&self, l2_hash: String, l1_head_hash: String) -> JsonResult<ProofResult> {
         let (l2_hash, l1_head_hash, user_req_id) =
             preprocessing(&l2_hash, &l1_head_hash).map_err(|e| {
@@ -119,22 +754,24 @@ impl Rpc for RpcImpl {
         tracing::info!("Received get - \"user_req_id\": {:?}", user_req_id);
 
         // Check if the proof is already stored.
-        let guard = self.task_lock.read().unwrap();
-        if let Some(proof) =
-            crate::utils::get_proof_by_local_id(&self.proof_db, &l2_hash, &l1_head_hash)
+        if let Some(proof_result) = self
+            .proof_db
+            .find_request_id(&l2_hash, &l1_head_hash)
+            .and_then(|request_id| self.proof_db.get_proof_result(&request_id))
         {
             tracing::info!("Proof was found in db: {:?}", user_req_id);
-            return Ok(ProofResult::new(
-                &self.proof_db.get_request_id(&l2_hash, &l1_head_hash).unwrap(),
-                RequestResult::Completed,
-                proof,
-            ));
+            return Ok(proof_result);
         }
         tracing::info!("Proof is not in db: {:?}", user_req_id);
-        drop(guard);
 
         // Check if it has been requested.
-        let _guard = self.task_lock.write().unwrap();
+        let _guard = self.task_lock.lock(&user_req_id);
+        match self.queue.status(&user_req_id) {
+            Some(RequestResult::Processing) => return Ok(ProofResult::processing(user_req_id)),
+            Some(RequestResult::Failed) => return Ok(ProofResult::failed(user_req_id)),
+            Some(RequestResult::Cancelled) => return Ok(ProofResult::cancelled(user_req_id)),
+            _ => {}
+        }
         let proof_result = match crate::utils::get_status_by_local_id(
             &self.client,
             &self.proof_db,
@@ -142,15 +779,209 @@ impl Rpc for RpcImpl {
             &l1_head_hash,
         ) {
             RequestResult::Completed => {
-                let proof = self.proof_db.get_proof(&l2_hash, &l1_head_hash).unwrap();
+                let proof = self.proof_db.get_proof(&l2_hash, &l1_head_hash).ok_or_else(|| {
+                    tracing::error!("The completed proof cannot be read: {:?}", user_req_id);
+                    jsonrpc_core::Error::internal_error()
+                })?;
                 ProofResult::new(&user_req_id, RequestResult::Completed, proof)
             }
             RequestResult::Processing => ProofResult::processing(user_req_id),
             RequestResult::None => ProofResult::none(),
             RequestResult::Failed => ProofResult::failed(user_req_id),
+            RequestResult::Cancelled => ProofResult::cancelled(user_req_id),
         };
         tracing::info!("return the proof result: {:?}", proof_result);
 
         Ok(proof_result)
     }
+
+    fn get_proofs(
+        &self,
+        items: Vec<ProofRequestItem>,
+    ) -> JsonResult<Vec<BatchItemResult<ProofResult>>> {
+        check_batch_size(items.len())?;
+        tracing::info!("Received batch get - {} item(s)", items.len());
+
+        Ok(run_concurrently(items, |item| self.get_proof(item.l2_hash, item.l1_head_hash).into()))
+    }
+
+    fn get_proof_by_request_id(&self, request_id: String) -> JsonResult<RequestProofResult> {
+        let request_id = B256::from_str(&request_id).map_err(|e| {
+            tracing::error!("Invalid parameters - \"request_id\": {:?}", request_id);
+            ProverError::invalid_input_hash(e.to_string()).to_json_error()
+        })?;
+        tracing::info!("Received get by request id - \"request_id\": {:?}", request_id);
+
+        let (l2_hash, l1_head_hash) =
+            self.proof_db.get_hashes_by_request_id(&request_id).ok_or_else(|| {
+                tracing::error!("Unknown request id: {:?}", request_id);
+                ProverError::request_not_found(request_id.to_string()).to_json_error_with_code()
+            })?;
+
+        // Check if the proof is already stored.
+        if let Some(proof_result) = self.proof_db.get_proof_result(&request_id) {
+            tracing::info!("Proof was found in db: {:?}", request_id);
+            return Ok(RequestProofResult::new(&l2_hash, &l1_head_hash, proof_result));
+        }
+
+        // NOTE(Ethan): lock by the same key as the methods which take the hashes.
+        let (_, _, user_req_id) =
+            preprocessing(&l2_hash.to_string(), &l1_head_hash.to_string()).unwrap();
+        let _guard = self.task_lock.lock(&user_req_id);
+        let proof_result =
+            match crate::utils::get_status_by_remote_id(&self.client, &self.proof_db, request_id) {
+                RequestResult::Completed => {
+                    let proof = self.proof_db.get_proof_by_id(&request_id).ok_or_else(|| {
+                        tracing::error!("The completed proof cannot be read: {:?}", request_id);
+                        jsonrpc_core::Error::internal_error()
+                    })?;
+                    ProofResult::new(&request_id, RequestResult::Completed, proof)
+                }
+                RequestResult::Processing => ProofResult::processing(request_id.to_string()),
+                RequestResult::None => ProofResult::none(),
+                RequestResult::Failed => ProofResult::failed(request_id.to_string()),
+                RequestResult::Cancelled => ProofResult::cancelled(request_id.to_string()),
+            };
+        tracing::info!("return the proof result: {:?}", proof_result);
+
+        Ok(RequestProofResult::new(&l2_hash, &l1_head_hash, proof_result))
+    }
+
+    fn subscribe_proof(
+        &self,
+        meta: CallerMeta,
+        subscriber: Subscriber<ProofResult>,
+        l2_hash: String,
+        l1_head_hash: String,
+    ) {
+        let user_req_id = match preprocessing(&l2_hash, &l1_head_hash) {
+            Ok((_, _, user_req_id)) => user_req_id,
+            Err(e) => {
+                tracing::error!(
+                    "Invalid parameters - \"l2_hash\": {:?}, \"l1_head_hash\": {:?}",
+                    l2_hash,
+                    l1_head_hash
+                );
+                let _ = subscriber
+                    .reject(ProverError::invalid_input_hash(e.to_string()).to_json_error());
+                return;
+            }
+        };
+
+        let id = self.subscriptions.next_id();
+        let sink = match subscriber.assign_id(id.clone()) {
+            Ok(sink) => sink,
+            Err(_) => return,
+        };
+        tracing::info!("Received subscribe - \"user_req_id\": {:?}, {:?}", user_req_id, id);
+
+        // NOTE(Ethan): the subscription id reaches the client only after this returns, so even the
+        // current status is pushed by the watcher rather than here.
+        self.subscriptions.add(id.clone(), l2_hash, l1_head_hash, sink);
+        if let Some(session) = &meta.session {
+            self.subscriptions.remove_on_close(id, session);
+        }
+    }
+
+    fn unsubscribe_proof(&self, _meta: Option<CallerMeta>, id: SubscriptionId) -> JsonResult<bool> {
+        tracing::info!("Received unsubscribe: {:?}", id);
+        Ok(self.subscriptions.remove(&id))
+    }
+
+    fn list_requests(&self, filter: Option<ListRequestsFilter>) -> JsonResult<ListRequestsResult> {
+        let filter = filter.unwrap_or_default();
+        tracing::info!("Received list requests: {:?}", filter);
+
+        let (requests, next_cursor) = self.proof_db.list_requests(
+            |metadata| filter.matches(metadata),
+            filter.cursor.as_ref(),
+            filter.limit(),
+        );
+        Ok(ListRequestsResult { requests, next_cursor })
+    }
+
+    fn discover(&self) -> JsonResult<Value> {
+        Ok(serde_json::from_str(OPENRPC_DOCUMENT).expect("the OpenRPC document is valid JSON"))
+    }
+
+    fn get_status(&self, l2_hash: String, l1_head_hash: String) -> JsonResult<StatusResult> {
+        let (l2_hash, l1_head_hash, user_req_id) =
+            preprocessing(&l2_hash, &l1_head_hash).map_err(|e| {
+                tracing::error!(
+                    "Invalid parameters - \"l2_hash\": {:?}, \"l1_head_hash\": {:?}",
+                    l2_hash,
+                    l1_head_hash
+                );
+                ProverError::invalid_input_hash(e.to_string()).to_json_error()
+            })?;
+        tracing::debug!("Received status - \"user_req_id\": {:?}", user_req_id);
+
+        if let Some(status) = self.queue.status(&user_req_id) {
+            return Ok(StatusResult::pending(status));
+        }
+
+        // NOTE(Ethan): this only reads the local store, so the status is as fresh as the last
+        // status query to the SP1 network.
+        let status = match self
+            .proof_db
+            .get_request_id(&l2_hash, &l1_head_hash)
+            .and_then(|request_id| self.proof_db.get_request_metadata(&request_id))
+        {
+            Some(metadata) => {
+                let estimated_proving_secs = match metadata.status {
+                    RequestResult::Processing => self.proof_db.estimated_proving_secs(),
+                    _ => 0,
+                };
+                StatusResult::new(&metadata, estimated_proving_secs)
+            }
+            None => StatusResult::none(),
+        };
+
+        Ok(status)
+    }
+
+    fn cancel_request(&self, l2_hash: String, l1_head_hash: String) -> JsonResult<RequestResult> {
+        let (l2_hash, l1_head_hash, user_req_id) =
+            preprocessing(&l2_hash, &l1_head_hash).map_err(|e| {
+                tracing::error!(
+                    "Invalid parameters - \"l2_hash\": {:?}, \"l1_head_hash\": {:?}",
+                    l2_hash,
+                    l1_head_hash
+                );
+                ProverError::invalid_input_hash(e.to_string()).to_json_error()
+            })?;
+        tracing::info!("Received cancel - \"user_req_id\": {:?}", user_req_id);
+
+        let _guard = self.task_lock.lock(&user_req_id);
+        // A request which has not reached the SP1 network yet has no request id, so it is taken
+        // out of the queue instead. Its callbacks are reported as failed.
+        if self.queue.cancel(&user_req_id) {
+            tracing::info!("Cancelled the request before its submission: {:?}", user_req_id);
+            self.fail_callbacks(&l2_hash, &l1_head_hash);
+            return Ok(RequestResult::Cancelled);
+        }
+        let request_id =
+            self.proof_db.get_request_id(&l2_hash, &l1_head_hash).ok_or_else(|| {
+                tracing::error!("There is no request to cancel: {:?}", user_req_id);
+                ProverError::request_not_found(user_req_id.to_string()).to_json_error_with_code()
+            })?;
+
+        // Only a request in progress can be cancelled.
+        let req_status =
+            crate::utils::get_status_by_remote_id(&self.client, &self.proof_db, request_id);
+        if req_status != RequestResult::Processing {
+            tracing::info!("The request is not in progress: {:?}, {:?}", user_req_id, req_status);
+            return Ok(req_status);
+        }
+
+        // NOTE(Ethan): the SP1 network client has no way to cancel a request, so the upstream
+        // request runs until its own timeout. Only the local record stops being polled.
+        self.proof_db.cancel_request(&request_id, &l2_hash, &l1_head_hash).map_err(|e| {
+            tracing::error!("Failed to cancel the request {:?}: {:?}", request_id, e);
+            jsonrpc_core::Error::internal_error()
+        })?;
+        tracing::info!("Cancelled the request: {:?}, {:?}", user_req_id, request_id);
+
+        Ok(RequestResult::Cancelled)
+    }
 }
@@ -1,9 +1,25 @@
+pub mod archive;
+pub mod cache;
+pub mod callbacks;
+pub mod crypto;
 pub mod errors;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
 pub mod interface;
+pub mod metrics;
+pub mod middleware;
+pub mod migrations;
 pub mod proof_db;
+pub mod queue;
+pub mod rest;
+pub mod scrub;
+pub mod subscriptions;
 pub mod types;
+pub mod uploads;
 pub mod utils;
 pub mod version;
+pub mod witness_generator;
 
 use std::env;
 
@@ -29,4 +45,9 @@ pub static VERIFICATION_KEY_HASH: Lazy<B256> = Lazy::new(|| {
 // NOTE(Ethan): equals to `DEFAULT_NETWORK_RPC_URL`` in sp1/creates/sdk/src/network/mod.rs
 pub const DEFAULT_NETWORK_RPC_URL: &str = "https://rpc.production.succinct.xyz/";
 pub const DEFAULT_PROOF_STORE_PATH: &str = "data/proof_store";
-pub const MAX_CYCLES: u64 = 1_000_000_000;
\ No newline at end of file
+pub const DEFAULT_UPLOAD_PATH: &str = "data/uploads";
+pub const MAX_CYCLES: u64 = 1_000_000_000;
+/// The largest HTTP request body, as it is sent.
+pub const MAX_REQUEST_BODY_SIZE: usize = 200 * 1024 * 1024;
+/// The largest HTTP request body or witness after it is decompressed.
+pub const MAX_DECOMPRESSED_SIZE: usize = 1024 * 1024 * 1024;
\ No newline at end of file
@@ -0,0 +1,200 @@
+use anyhow::Result;
+use jsonrpc_http_server::hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    Body, Response, StatusCode,
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, register_int_gauge_vec, Encoder, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+use sp1_sdk::network::proto::network::ProofRequest;
+use std::{collections::BTreeMap, time::Instant};
+
+use crate::{proof_db::ProofDB, types::RequestResult};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+pub static QUARANTINED_PROOFS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "prover_proxy_quarantined_proofs_total",
+        "Number of stored proofs quarantined by an integrity check"
+    )
+    .unwrap()
+});
+
+pub static SCRUBBED_PROOFS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "prover_proxy_scrubbed_proofs_total",
+        "Number of stored proofs checked by the scrubber"
+    )
+    .unwrap()
+});
+
+pub static PROOF_CACHE_HITS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("prover_proxy_proof_cache_hits_total", "Number of proof cache hits")
+        .unwrap()
+});
+
+pub static PROOF_CACHE_MISSES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("prover_proxy_proof_cache_misses_total", "Number of proof cache misses")
+        .unwrap()
+});
+
+pub static RPC_CALLS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "prover_proxy_rpc_calls_total",
+        "Number of calls of the methods, over JSON-RPC, REST and gRPC, by outcome",
+        &["method", "outcome"]
+    )
+    .unwrap()
+});
+
+pub static RPC_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "prover_proxy_rpc_duration_seconds",
+        "Duration of the calls of the methods",
+        &["method"]
+    )
+    .unwrap()
+});
+
+pub static SP1_SUBMISSION_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "prover_proxy_sp1_submission_duration_seconds",
+        "Duration of the proof requests to the SP1 network"
+    )
+    .unwrap()
+});
+
+pub static SP1_STATUS_QUERY_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "prover_proxy_sp1_status_query_duration_seconds",
+        "Duration of the status queries to the SP1 network"
+    )
+    .unwrap()
+});
+
+pub static SP1_NETWORK_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "prover_proxy_sp1_network_errors_total",
+        "Number of failed calls to the SP1 network, by operation",
+        &["operation"]
+    )
+    .unwrap()
+});
+
+pub static TIME_TO_PROOF: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "prover_proxy_time_to_proof_seconds",
+        "Time from the creation of a request on the SP1 network to its fulfillment",
+        vec![300.0, 600.0, 900.0, 1200.0, 1800.0, 2700.0, 3600.0, 5400.0, 7200.0, 10800.0]
+    )
+    .unwrap()
+});
+
+pub static REQUESTS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "prover_proxy_requests",
+        "Number of requests in the proof store, by state",
+        &["state"]
+    )
+    .unwrap()
+});
+
+pub static STORE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("prover_proxy_store_requests", "Number of requests in the proof store")
+        .unwrap()
+});
+
+pub static QUEUE_DEPTH: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("prover_proxy_queue_depth", "Number of requests waiting to be submitted")
+        .unwrap()
+});
+
+/// Records a call of `method`, which is named as in JSON-RPC whichever interface it came from.
+pub fn observe_rpc(method: &str, started: Instant, ok: bool) {
+    let outcome = if ok { "ok" } else { "error" };
+    RPC_CALLS.with_label_values(&[method, outcome]).inc();
+    RPC_DURATION.with_label_values(&[method]).observe(started.elapsed().as_secs_f64());
+}
+
+/// Records a call to the SP1 network which started at `started`, and counts it if it failed.
+pub fn observe_sp1<T, E>(
+    operation: &str,
+    duration: &Histogram,
+    started: Instant,
+    result: &std::result::Result<T, E>,
+) {
+    duration.observe(started.elapsed().as_secs_f64());
+    if result.is_err() {
+        SP1_NETWORK_ERRORS.with_label_values(&[operation]).inc();
+    }
+}
+
+/// Records the time which the SP1 network took to fulfill `request`, as the network reports it.
+pub fn observe_time_to_proof(request: &ProofRequest) {
+    if let Some(fulfilled_at) = request.fulfilled_at {
+        TIME_TO_PROOF.observe(fulfilled_at.saturating_sub(request.created_at) as f64);
+    }
+}
+
+/// Moves a request from the state `from`, if it was counted, to `to` in `REQUESTS`.
+pub fn observe_state(from: Option<&RequestResult>, to: &RequestResult) {
+    if let Some(from) = from {
+        REQUESTS.with_label_values(&[&format!("{:?}", from)]).dec();
+    }
+    REQUESTS.with_label_values(&[&format!("{:?}", to)]).inc();
+}
+
+/// Counts the requests in the store by state, at startup and after each scrub. The gauges follow
+/// the state transitions in between, so that a scrape does not read the store.
+pub fn count_requests(proof_db: &ProofDB) {
+    let request_ids = proof_db.request_ids();
+    let mut states: BTreeMap<String, i64> = [
+        RequestResult::None,
+        RequestResult::Processing,
+        RequestResult::Completed,
+        RequestResult::Failed,
+        RequestResult::Cancelled,
+    ]
+    .iter()
+    .map(|state| (format!("{:?}", state), 0))
+    .collect();
+    for request_id in &request_ids {
+        if let Some(metadata) = proof_db.get_request_metadata(request_id) {
+            *states.entry(format!("{:?}", metadata.status)).or_default() += 1;
+        }
+    }
+    for (state, count) in states {
+        REQUESTS.with_label_values(&[&state]).set(count);
+    }
+    STORE_SIZE.set(request_ids.len() as i64);
+}
+
+/// Encodes every metric in the text format of Prometheus.
+pub fn gather() -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+    Ok(buffer)
+}
+
+/// Answers `/metrics`.
+pub fn handle() -> Response<Body> {
+    match gather() {
+        Ok(body) => {
+            let mut response = Response::new(Body::from(body));
+            let content_type = HeaderValue::from_str(TextEncoder::new().format_type())
+                .expect("the format type is a valid header value");
+            response.headers_mut().insert(CONTENT_TYPE, content_type);
+            response
+        }
+        Err(e) => {
+            tracing::error!("Failed to gather the metrics: {:?}", e);
+            let mut response = Response::new(Body::from(e.to_string()));
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        }
+    }
+}
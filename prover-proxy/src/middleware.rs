@@ -0,0 +1,189 @@
+use jsonrpc_http_server::{
+    hyper::{
+        self,
+        header::{CONTENT_ENCODING, CONTENT_LENGTH},
+        Body, Request, Response, StatusCode,
+    },
+    RequestMiddleware, RequestMiddlewareAction,
+};
+use std::sync::Arc;
+
+use crate::{
+    health::HealthCheck,
+    interface::{CallerMeta, RpcMetrics},
+    metrics::{self, METRICS_PATH},
+    rest::{json_response, RestApi, Route},
+    utils::{gunzip, unzstd},
+    MAX_REQUEST_BODY_SIZE,
+};
+
+/// Handles HTTP requests before they reach the JSON-RPC handler.
+#[derive(Default)]
+pub struct ProxyMiddleware {
+    rest_api: Option<RestApi>,
+    health: Option<Arc<HealthCheck>>,
+    metrics: bool,
+    rpc: Option<Arc<RpcHandler>>,
+}
+
+/// The JSON-RPC handler of the HTTP server.
+pub type RpcHandler = jsonrpc_core::MetaIoHandler<CallerMeta, RpcMetrics>;
+
+impl ProxyMiddleware {
+    /// Serves the REST API under `/v1`, along with its OpenAPI document.
+    pub fn with_rest_api(mut self, rest_api: RestApi) -> Self {
+        self.rest_api = Some(rest_api);
+        self
+    }
+
+    /// Serves `/healthz` and `/readyz`.
+    pub fn with_health(mut self, health: Arc<HealthCheck>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Serves the Prometheus metrics at `/metrics`.
+    pub fn with_metrics(mut self) -> Self {
+        self.metrics = true;
+        self
+    }
+
+    /// Answers compressed JSON-RPC requests with `rpc`, which is built like the server's handler.
+    pub fn with_rpc(mut self, rpc: Arc<RpcHandler>) -> Self {
+        self.rpc = Some(rpc);
+        self
+    }
+}
+
+impl RequestMiddleware for ProxyMiddleware {
+    fn on_request(&self, request: Request<Body>) -> RequestMiddlewareAction {
+        let decompress = match content_decoder(&request) {
+            Ok(decompress) => decompress,
+            Err(response) => return (*response).into(),
+        };
+        let path = request.uri().path().to_string();
+        if let Some(health) = self.health.as_ref().filter(|_| HealthCheck::is_health_path(&path)) {
+            let health = health.clone();
+            return RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::pin(async move { Ok(health.handle(path).await) }),
+            };
+        }
+        if self.metrics && path == METRICS_PATH {
+            return RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::pin(async move { Ok(metrics::handle()) }),
+            };
+        }
+        let rest_api = self.rest_api.clone().filter(|_| Route::is_rest_path(&path));
+        if let Some(decompress) = decompress {
+            // NOTE(Ethan): the decompressed body may exceed `MAX_REQUEST_BODY_SIZE`, which the
+            // server enforces on whatever the middleware passes on, so it is answered here.
+            let rpc = self.rpc.clone();
+            return RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::pin(async move {
+                    Ok(handle_compressed(request, decompress, rest_api, rpc).await)
+                }),
+            };
+        }
+        match rest_api {
+            Some(rest_api) => RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::pin(async move { Ok(rest_api.handle(request).await) }),
+            },
+            None => request.into(),
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: String) -> Response<Body> {
+    tracing::error!("Rejected a request with {}: {}", status, message);
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    response
+}
+
+type Decompress = fn(&[u8]) -> anyhow::Result<Vec<u8>>;
+
+/// Returns how to decompress the body of a request which has `Content-Encoding: gzip` or `zstd`,
+/// or `None` if it is not compressed.
+fn content_decoder(request: &Request<Body>) -> Result<Option<Decompress>, Box<Response<Body>>> {
+    let content_length = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if content_length.map_or(false, |length| length > MAX_REQUEST_BODY_SIZE) {
+        let message = format!("The body exceeds {} bytes", MAX_REQUEST_BODY_SIZE);
+        return Err(Box::new(error_response(StatusCode::PAYLOAD_TOO_LARGE, message)));
+    }
+
+    let encoding = match request.headers().get(CONTENT_ENCODING) {
+        Some(value) => value.to_str().unwrap_or_default().trim().to_ascii_lowercase(),
+        None => return Ok(None),
+    };
+    match encoding.as_str() {
+        "identity" => Ok(None),
+        "gzip" => Ok(Some(gunzip)),
+        "zstd" => Ok(Some(unzstd)),
+        _ => {
+            let message = format!("Unsupported content encoding: {:?}", encoding);
+            Err(Box::new(error_response(StatusCode::UNSUPPORTED_MEDIA_TYPE, message)))
+        }
+    }
+}
+
+/// Answers a compressed request. The compressed body is limited to `MAX_REQUEST_BODY_SIZE`, and
+/// the decompressed one to `MAX_DECOMPRESSED_SIZE`.
+async fn handle_compressed(
+    request: Request<Body>,
+    decompress: Decompress,
+    rest_api: Option<RestApi>,
+    rpc: Option<Arc<RpcHandler>>,
+) -> Response<Body> {
+    let (parts, body) = request.into_parts();
+    let data = match read_body_limited(body, MAX_REQUEST_BODY_SIZE).await {
+        Ok(data) => data,
+        Err(e) => return error_response(StatusCode::PAYLOAD_TOO_LARGE, e.to_string()),
+    };
+    let body = match tokio::task::spawn_blocking(move || decompress(&data)).await {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    if let Some(rest_api) = rest_api {
+        return rest_api.handle_body(parts, body).await;
+    }
+    let rpc = match rpc {
+        Some(rpc) => rpc,
+        None => {
+            let message = "Compressed JSON-RPC requests are not served".to_string();
+            return error_response(StatusCode::UNSUPPORTED_MEDIA_TYPE, message);
+        }
+    };
+    let request = match String::from_utf8(body) {
+        Ok(request) => request,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    // NOTE(Ethan): the handlers block on the SP1 network, so they do not run on the server.
+    let meta = CallerMeta::from_headers(&parts.headers);
+    match tokio::task::spawn_blocking(move || rpc.handle_request_sync(&request, meta)).await {
+        Ok(response) => json_response(StatusCode::OK, response.unwrap_or_default().into_bytes()),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+pub(crate) async fn read_body_limited(mut body: Body, limit: usize) -> anyhow::Result<Vec<u8>> {
+    use hyper::body::HttpBody;
+
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(&chunk?);
+        if data.len() > limit {
+            anyhow::bail!("The body exceeds {} bytes", limit);
+        }
+    }
+    Ok(data)
+}
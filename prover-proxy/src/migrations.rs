@@ -0,0 +1,171 @@
+use alloy_primitives::B256;
+use anyhow::{bail, Result};
+use sp1_sdk::{
+    network::{proto::network::FulfillmentStatus, NetworkClient},
+    SP1_CIRCUIT_VERSION as SP1_SDK_VERSION,
+};
+
+use crate::{
+    proof_db::{ProofDB, StoredProof, RECORD_EXPIRING_SECS, SCHEMA_VERSION},
+    utils::{block_on, now_secs},
+    VERIFICATION_KEY_HASH,
+};
+
+static LEGACY_REQUESTS_PAGE_SIZE: u32 = 100;
+
+/// A step which upgrades the proof store from `from` to `to`.
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    /// Returns the number of records which were (or, on a dry run, would be) rewritten.
+    pub run: fn(&ProofDB, &MigrationContext) -> Result<usize>,
+}
+
+/// What every migration step is run with.
+pub struct MigrationContext<'a> {
+    /// Only count the records which would be rewritten.
+    pub dry_run: bool,
+    /// Ids of the requests which may have a proof in a v0 store, which has no index to enumerate.
+    pub legacy_request_ids: &'a [B256],
+}
+
+/// Registered migrations, in the order they are applied.
+pub static MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    to: 1,
+    description: "seal the proofs of the baseline store and index them",
+    run: migrate_v0_to_v1,
+}];
+
+#[derive(Clone, Debug)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    pub affected: usize,
+    pub dry_run: bool,
+}
+
+impl std::fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let verb = if self.dry_run { "would rewrite" } else { "rewrote" };
+        write!(
+            f,
+            "v{} -> v{} ({}): {} {} record(s)",
+            self.from, self.to, self.description, verb, self.affected
+        )
+    }
+}
+
+/// Applies every pending migration in order. On a dry run nothing is written, and the returned
+/// reports describe what would happen. The proofs of a v0 store are looked up by
+/// `legacy_request_ids`, e.g. from `list_legacy_request_ids`.
+pub fn run_migrations(
+    proof_db: &ProofDB,
+    legacy_request_ids: &[B256],
+    dry_run: bool,
+) -> Result<Vec<MigrationReport>> {
+    let mut version = proof_db.schema_version()?;
+    if version > SCHEMA_VERSION {
+        bail!(
+            "The proof store has schema version {} which is newer than {}; refusing to downgrade",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+
+    let context = MigrationContext { dry_run, legacy_request_ids };
+    let mut reports = Vec::new();
+    while version < SCHEMA_VERSION {
+        let migration = match MIGRATIONS.iter().find(|m| m.from == version) {
+            Some(migration) => migration,
+            None => bail!("No migration is registered from schema version {}", version),
+        };
+        tracing::info!("Migrating the proof store: v{} -> v{}", migration.from, migration.to);
+        let affected = (migration.run)(proof_db, &context)?;
+        if !dry_run {
+            proof_db.set_schema_version(migration.to)?;
+        }
+        reports.push(MigrationReport {
+            from: migration.from,
+            to: migration.to,
+            description: migration.description,
+            affected,
+            dry_run,
+        });
+        version = migration.to;
+    }
+
+    Ok(reports)
+}
+
+/// Returns the ids of stored proofs which can no longer be decoded by the linked SP1 version.
+pub fn find_undecodable_proofs(proof_db: &ProofDB) -> Vec<B256> {
+    proof_db
+        .request_ids()
+        .into_iter()
+        .filter(|request_id| match proof_db.get_stored_proof(request_id) {
+            Ok(Some(proof)) => match proof.decode() {
+                Ok(_) => false,
+                Err(e) => {
+                    tracing::warn!("Proof is not decodable by sp1 {}: {:?}", SP1_SDK_VERSION, e);
+                    true
+                }
+            },
+            Ok(None) => false,
+            Err(_) => true,
+        })
+        .collect()
+}
+
+/// Lists the requests of the program which the SP1 network fulfilled while their proofs may still
+/// be kept in a v0 store.
+pub fn list_legacy_request_ids(client: &NetworkClient) -> Result<Vec<B256>> {
+    let from = now_secs().saturating_sub(RECORD_EXPIRING_SECS as u64);
+    let mut request_ids = Vec::new();
+    for page in 1.. {
+        let response = block_on(client.get_filtered_proof_requests(
+            None,
+            Some(FulfillmentStatus::Fulfilled as i32),
+            None,
+            None,
+            Some(VERIFICATION_KEY_HASH.to_vec()),
+            None,
+            None,
+            Some(from),
+            None,
+            Some(LEGACY_REQUESTS_PAGE_SIZE),
+            Some(page),
+            None,
+        ))?;
+        let requests = response.requests;
+        request_ids.extend(requests.iter().map(|request| B256::from_slice(&request.request_id)));
+        if requests.len() < LEGACY_REQUESTS_PAGE_SIZE as usize {
+            break;
+        }
+    }
+    Ok(request_ids)
+}
+
+// NOTE(Ethan): the v0 store has no index to enumerate, so its proofs are looked up by
+// `legacy_request_ids`. A proof which is missed here is still upgraded by
+// `ProofDB::get_stored_proof` on its first read. Its mappings from hashes are kept raw, as they
+// cannot be enumerated either, and are read as they are by `ProofDB::get_request_id`.
+fn migrate_v0_to_v1(proof_db: &ProofDB, context: &MigrationContext) -> Result<usize> {
+    let mut affected = 0;
+    for request_id in context.legacy_request_ids {
+        let proof = match proof_db.get_legacy_proof(request_id) {
+            Some(proof) => proof,
+            None => continue,
+        };
+        affected += 1;
+        if context.dry_run {
+            continue;
+        }
+        proof_db.put(&ProofDB::proof_key(request_id), &StoredProof::new(&proof)?)?;
+        proof_db.track_request_id(request_id)?;
+    }
+
+    Ok(affected)
+}
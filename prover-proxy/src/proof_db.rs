@@ -1,18 +1,288 @@
-use alloy_primitives::B256;
-use anyhow::{anyhow, Result};
+use alloy_primitives::{keccak256, B256};
+use anyhow::{anyhow, bail, Result};
+use fs2::FileExt;
 use kroma_zkvm_common::db::FileDB;
-use sp1_sdk::SP1ProofWithPublicValues;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1_CIRCUIT_VERSION as SP1_SDK_VERSION};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    archive::ProofArchive,
+    cache::ProofCache,
+    crypto::{Ciphertext, KeyRing},
+    types::{ProofResult, RequestMetadata, RequestResult},
+};
 
 static CAPACITY: usize = 1000;
-static VALUE_EXPIRING_SECS: usize = 7 * 24 * 60 * 60; // 604800; 7 days in seconds.
+/// How long a record is kept after it is written.
+pub(crate) static RECORD_EXPIRING_SECS: usize = 7 * 24 * 60 * 60; // 604800; 7 days in seconds.
+static DEFAULT_PROVING_SECS: u64 = 60 * 60;
+static PROVING_SECS_SAMPLES: usize = 50;
+
+/// The version of the on-disk record format. Bump it and register a step in `migrations.rs`
+/// whenever the layout of a stored record changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The version of the objects in the archive. It is kept apart from `SCHEMA_VERSION`, since the
+/// archive is shared by proxies of different versions and is never migrated; bump it only when
+/// the layout of an archived object changes, and keep reading the older ones.
+pub const ARCHIVE_VERSION: u32 = 1;
+/// The version of the layout of `RequestMetadata` in an archived proof.
+pub const ARCHIVED_METADATA_VERSION: u32 = 1;
+
+const SCHEMA_VERSION_KEY: &[u8] = b"meta:schema_version";
+const REQUEST_IDS_KEY: &[u8] = b"meta:request_ids";
+const PROOF_KEY_PREFIX: &[u8] = b"proof:";
+const REQUEST_KEY_PREFIX: &[u8] = b"request:";
+const QUARANTINE_KEY: &[u8] = b"meta:quarantine";
+const CALLBACKS_KEY: &[u8] = b"meta:callbacks";
+const HISTORY_KEY_PREFIX: &[u8] = b"history:";
+const IDEMPOTENCY_KEY_PREFIX: &[u8] = b"idempotency:";
+
+/// Every record written by `ProofDB` is wrapped with the schema version it was written with, so
+/// that a layout change is detected instead of being decoded as garbage.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u32,
+    pub body: Vec<u8>,
+}
+
+/// The body of an envelope: a payload along with its checksum, so that disk rot is detected
+/// instead of being decoded. The payload is encrypted when `key_id` is set, and the checksum
+/// covers the stored bytes so that a scrub does not need the key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sealed {
+    pub checksum: B256,
+    pub key_id: Option<String>,
+    pub nonce: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+impl Sealed {
+    pub fn new(payload: Vec<u8>) -> Self {
+        Self { checksum: keccak256(&payload), key_id: None, nonce: Vec::new(), payload }
+    }
+
+    pub fn from_ciphertext(ciphertext: Ciphertext) -> Self {
+        Self {
+            checksum: keccak256(&ciphertext.data),
+            key_id: Some(ciphertext.key_id),
+            nonce: ciphertext.nonce,
+            payload: ciphertext.data,
+        }
+    }
+
+    /// Verifies the checksum and returns the payload, decrypted with `key_ring` if it is
+    /// encrypted. `aad` must be the one which the payload was encrypted with.
+    pub fn open(self, key_ring: Option<&KeyRing>, aad: &[u8]) -> Result<Vec<u8>> {
+        let checksum = keccak256(&self.payload);
+        if checksum != self.checksum {
+            bail!("Checksum mismatch: expected {}, computed {}", self.checksum, checksum);
+        }
+        match (self.key_id, key_ring) {
+            (None, _) => Ok(self.payload),
+            (Some(key_id), Some(key_ring)) => {
+                key_ring.decrypt(&Ciphertext { key_id, nonce: self.nonce, data: self.payload }, aad)
+            }
+            (Some(key_id), None) => {
+                bail!("The record is encrypted with the key {:?}, but no key is given", key_id)
+            }
+        }
+    }
+}
+
+/// A completed request as it is kept in the archive.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedProof {
+    pub request_id: B256,
+    pub metadata: Option<ArchivedMetadata>,
+    pub proof: StoredProof,
+}
+
+/// The metadata of an archived proof, tagged with the version of its layout so that the proof
+/// can still be restored by a proxy which does not know the layout.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedMetadata {
+    pub version: u32,
+    pub payload: Vec<u8>,
+}
+
+impl ArchivedMetadata {
+    pub fn new(metadata: &RequestMetadata) -> Result<Self> {
+        let payload = bincode::serialize(metadata)
+            .map_err(|e| anyhow!("Failed to serialize request metadata: {}", e))?;
+        Ok(Self { version: ARCHIVED_METADATA_VERSION, payload })
+    }
+
+    pub fn decode(&self) -> Result<RequestMetadata> {
+        match self.version {
+            ARCHIVED_METADATA_VERSION => bincode::deserialize(&self.payload)
+                .map_err(|e| anyhow!("Failed to deserialize request metadata: {}", e)),
+            version => bail!("Unknown version of archived request metadata: {}", version),
+        }
+    }
+}
+
+/// A proof which failed its integrity check and is no longer served.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct QuarantinedProof {
+    pub request_id: B256,
+    pub reason: String,
+    pub quarantined_at: u64,
+}
+
+/// A callback which is delivered once the request of its hashes is finished. A request can have
+/// a callback per url.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PendingCallback {
+    /// Tells the callback apart from a later one of the same hashes and url, which replaces it
+    /// while it may be in delivery.
+    pub id: u64,
+    pub l2_hash: B256,
+    pub l1_head_hash: B256,
+    /// The request which is reported, once it reaches the SP1 network.
+    pub request_id: Option<B256>,
+    pub url: String,
+    /// Set when the request failed before it reached the SP1 network, so that the failure is
+    /// reported instead.
+    pub failed: bool,
+    /// The number of failed deliveries.
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+}
+
+impl PendingCallback {
+    pub fn new(l2_hash: &B256, l1_head_hash: &B256, request_id: Option<B256>, url: String) -> Self {
+        Self {
+            id: crate::utils::now_nanos(),
+            l2_hash: *l2_hash,
+            l1_head_hash: *l1_head_hash,
+            request_id,
+            url,
+            failed: false,
+            attempts: 0,
+            next_attempt_at: 0,
+        }
+    }
+
+    fn is_for(&self, l2_hash: &B256, l1_head_hash: &B256) -> bool {
+        self.l2_hash == *l2_hash && self.l1_head_hash == *l1_head_hash
+    }
+
+    fn is_same(&self, other: &Self) -> bool {
+        self.is_for(&other.l2_hash, &other.l1_head_hash) && self.url == other.url
+    }
+}
+
+/// A proof along with the SP1 version which serialized it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredProof {
+    pub sp1_version: String,
+    pub proof: Vec<u8>,
+}
+
+impl StoredProof {
+    pub fn new(proof: &SP1ProofWithPublicValues) -> Result<Self> {
+        let proof =
+            bincode::serialize(proof).map_err(|e| anyhow!("Failed to serialize proof: {}", e))?;
+        Ok(Self { sp1_version: SP1_SDK_VERSION.to_string(), proof })
+    }
+
+    pub fn decode(&self) -> Result<SP1ProofWithPublicValues> {
+        bincode::deserialize(&self.proof).map_err(|e| {
+            anyhow!(
+                "Failed to decode proof written by sp1 {} (current: {}): {}",
+                self.sp1_version,
+                SP1_SDK_VERSION,
+                e
+            )
+        })
+    }
+}
+
+/// The exclusive lock of a store, held until it is dropped. See `ProofDB::lock`.
+pub struct StoreLock {
+    _file: File,
+}
 
 pub struct ProofDB {
     db: FileDB,
+    index_lock: Mutex<()>,
+    key_ring: Option<KeyRing>,
+    proof_cache: ProofCache,
+    archive: Option<ProofArchive>,
+    /// The files next to the store. See `ProofDB::sidecar_path`.
+    schema_path: PathBuf,
+    index_path: PathBuf,
+    quarantine_path: PathBuf,
+    outbox_path: PathBuf,
+    lock_path: PathBuf,
+    /// The quarantine, read from its file on first use. See `ProofDB::with_quarantine`.
+    quarantine: Mutex<Option<Vec<QuarantinedProof>>>,
+    /// The durations of the recent completed requests, oldest first. It is read from the store
+    /// for the first estimate, and kept up to date by completions from then on.
+    proving_secs: Mutex<Option<VecDeque<u64>>>,
 }
 
 impl ProofDB {
     pub fn new(db_file_path: &str) -> Self {
-        Self { db: FileDB::new(db_file_path.into(), CAPACITY, VALUE_EXPIRING_SECS) }
+        Self {
+            db: FileDB::new(db_file_path.into(), CAPACITY, RECORD_EXPIRING_SECS),
+            index_lock: Mutex::new(()),
+            key_ring: None,
+            proof_cache: ProofCache::default(),
+            archive: None,
+            schema_path: Self::sidecar_path(db_file_path, "schema"),
+            index_path: Self::sidecar_path(db_file_path, "index"),
+            quarantine_path: Self::sidecar_path(db_file_path, "quarantine"),
+            outbox_path: Self::sidecar_path(db_file_path, "outbox"),
+            lock_path: Self::sidecar_path(db_file_path, "lock"),
+            quarantine: Mutex::new(None),
+            proving_secs: Mutex::new(None),
+        }
+    }
+
+    /// Encrypts values written from now on with the active key of `key_ring`.
+    pub fn with_key_ring(mut self, key_ring: Option<KeyRing>) -> Self {
+        self.key_ring = key_ring;
+        self
+    }
+
+    /// Writes completed proofs through to `archive`, and reads them back from it on a miss.
+    pub fn with_archive(mut self, archive: Option<ProofArchive>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Caches up to `capacity` ready-to-serve proof results; zero disables the cache.
+    pub fn with_proof_cache(mut self, capacity: usize) -> Self {
+        self.proof_cache = ProofCache::new(capacity);
+        self
+    }
+
+    // NOTE(Ethan): what must outlive the records of `FileDB`, which expire and are evicted past
+    // its capacity, is kept in files next to the store instead: the schema version, the request
+    // index, the quarantine and the callback outbox.
+    fn sidecar_path(db_file_path: &str, extension: &str) -> PathBuf {
+        PathBuf::from(format!("{}.{}", db_file_path.trim_end_matches('/'), extension))
+    }
+
+    /// Takes the exclusive lock of the store, which fails while another process holds it. The
+    /// server holds it while it runs, so that the commands do not write the store under it.
+    pub fn lock(&self) -> Result<StoreLock> {
+        let file =
+            OpenOptions::new().create(true).truncate(false).write(true).open(&self.lock_path)?;
+        file.try_lock_exclusive().map_err(|e| {
+            tracing::error!("Failed to lock the proof store: {:?}: {:?}", self.lock_path, e);
+            anyhow!("The proof store is in use by another process: {:?}", self.lock_path)
+        })?;
+        Ok(StoreLock { _file: file })
     }
 
     fn build_key(l2_hash: &B256, l1_head_hash: &B256) -> Vec<u8> {
@@ -22,12 +292,275 @@ impl ProofDB {
         key
     }
 
+    fn build_prefixed_key(prefix: &[u8], request_id: &B256) -> Vec<u8> {
+        let mut key = Vec::with_capacity(prefix.len() + 32);
+        key.extend_from_slice(prefix);
+        key.extend_from_slice(request_id.as_slice());
+        key
+    }
+
+    pub(crate) fn proof_key(request_id: &B256) -> Vec<u8> {
+        Self::build_prefixed_key(PROOF_KEY_PREFIX, request_id)
+    }
+
+    fn request_key(request_id: &B256) -> Vec<u8> {
+        Self::build_prefixed_key(REQUEST_KEY_PREFIX, request_id)
+    }
+
+    // NOTE(Ethan): proofs stored before the schema versioning were keyed by this.
     fn convert_req_id_as_key<T: ToString>(request_id: &T) -> Vec<u8> {
         bincode::serialize(&request_id.to_string())
             .map_err(|e| anyhow!("Failed to serialize value: {}", e))
             .unwrap()
     }
 
+    fn get_envelope(&self, key: &[u8]) -> Option<Envelope> {
+        self.db.get(key)
+    }
+
+    fn set_envelope(&self, key: &[u8], envelope: &Envelope) -> Result<()> {
+        self.db.set(key, envelope)
+    }
+
+    /// Reads a record as it is stored, without an envelope.
+    fn get_raw<T: DeserializeOwned>(&self, key: &[u8]) -> Option<T> {
+        self.db.get(key)
+    }
+
+    fn seal<T: Serialize>(&self, key: &[u8], value: &T) -> Result<Envelope> {
+        let payload =
+            bincode::serialize(value).map_err(|e| anyhow!("Failed to serialize value: {}", e))?;
+        let sealed = match &self.key_ring {
+            Some(key_ring) => Sealed::from_ciphertext(key_ring.encrypt(&payload, key)?),
+            None => Sealed::new(payload),
+        };
+        let body =
+            bincode::serialize(&sealed).map_err(|e| anyhow!("Failed to serialize value: {}", e))?;
+        Ok(Envelope { version: SCHEMA_VERSION, body })
+    }
+
+    fn unseal<T: DeserializeOwned>(&self, key: &[u8], envelope: Envelope) -> Result<T> {
+        if envelope.version != SCHEMA_VERSION {
+            bail!(
+                "Record has schema version {} but {} is expected; run `prover-proxy migrate`",
+                envelope.version,
+                SCHEMA_VERSION
+            );
+        }
+        self.open_envelope(key, &envelope)
+    }
+
+    /// Opens a record whatever its schema version is, as long as it is `Sealed`.
+    fn open_envelope<T: DeserializeOwned>(&self, key: &[u8], envelope: &Envelope) -> Result<T> {
+        let sealed: Sealed = bincode::deserialize(&envelope.body)
+            .map_err(|e| anyhow!("Failed to deserialize value: {}", e))?;
+        bincode::deserialize(&sealed.open(self.key_ring.as_ref(), key)?)
+            .map_err(|e| anyhow!("Failed to deserialize value: {}", e))
+    }
+
+    pub(crate) fn put<T: Serialize>(&self, key: &[u8], value: &T) -> Result<()> {
+        self.db.set(key, &self.seal(key, value)?)
+    }
+
+    fn fetch<T: DeserializeOwned>(&self, key: &[u8]) -> Result<Option<T>> {
+        match self.get_envelope(key) {
+            Some(envelope) => self.unseal(key, envelope).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads a record which is empty until it is first written, e.g. an index.
+    fn fetch_or_default<T: DeserializeOwned + Default>(&self, key: &[u8]) -> Result<T> {
+        Ok(self.fetch(key)?.unwrap_or_default())
+    }
+
+    /// Reads a record which is empty until it is first written, logging a failure as empty.
+    fn fetch_logged<T: DeserializeOwned + Default>(&self, key: &[u8], name: &str) -> T {
+        self.fetch_or_default(key).unwrap_or_else(|e| {
+            tracing::error!("Failed to load the {}: {:?}", name, e);
+            T::default()
+        })
+    }
+
+    fn put_archived<T: Serialize>(
+        &self,
+        archive: &ProofArchive,
+        name: &str,
+        value: &T,
+    ) -> Result<()> {
+        let envelope = Envelope { version: ARCHIVE_VERSION, ..self.seal(name.as_bytes(), value)? };
+        let data = bincode::serialize(&envelope)
+            .map_err(|e| anyhow!("Failed to serialize value: {}", e))?;
+        archive.put(name, data)
+    }
+
+    fn fetch_archived<T: DeserializeOwned>(
+        &self,
+        archive: &ProofArchive,
+        name: &str,
+    ) -> Result<Option<T>> {
+        let data = match archive.get(name)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let envelope: Envelope = bincode::deserialize(&data)
+            .map_err(|e| anyhow!("Failed to deserialize value: {}", e))?;
+        // NOTE(Ethan): decode the older layouts here when `ARCHIVE_VERSION` is bumped.
+        match envelope.version {
+            ARCHIVE_VERSION => self.open_envelope(name.as_bytes(), &envelope).map(Some),
+            version => bail!(
+                "Archived object {} has version {} but only {} is known",
+                name,
+                version,
+                ARCHIVE_VERSION
+            ),
+        }
+    }
+
+    /// Writes a completed proof through to the archive, along with the mapping from its hashes
+    /// when they are known.
+    fn archive_proof(&self, request_id: &B256, proof: &StoredProof) -> Result<()> {
+        let archive = match &self.archive {
+            Some(archive) => archive,
+            None => return Ok(()),
+        };
+        let metadata = self.get_request_metadata(request_id);
+        let archived = ArchivedProof {
+            request_id: *request_id,
+            metadata: metadata.as_ref().map(ArchivedMetadata::new).transpose()?,
+            proof: proof.clone(),
+        };
+        self.put_archived(archive, &Self::archived_proof_name(request_id), &archived)?;
+        if let Some(metadata) = metadata {
+            let name = Self::archived_request_name(&metadata.l2_hash, &metadata.l1_head_hash);
+            self.put_archived(archive, &name, request_id)?;
+        }
+        tracing::info!("Archived the proof of {:?}", request_id);
+        Ok(())
+    }
+
+    /// Restores a completed request from the archive into the local store.
+    fn restore_proof(&self, request_id: &B256) -> Result<Option<StoredProof>> {
+        let archive = match &self.archive {
+            Some(archive) => archive,
+            None => return Ok(None),
+        };
+        let archived: ArchivedProof =
+            match self.fetch_archived(archive, &Self::archived_proof_name(request_id))? {
+                Some(archived) => archived,
+                None => return Ok(None),
+            };
+        if let Some(metadata) = &archived.metadata {
+            match metadata.decode() {
+                Ok(metadata) if self.get_request_metadata(request_id).is_none() => {
+                    self.write_request_metadata(&metadata, None)?;
+                }
+                Ok(_) => {}
+                // The proof is still served without its metadata.
+                Err(e) => {
+                    tracing::warn!("Skipped the archived metadata of {:?}: {:?}", request_id, e)
+                }
+            }
+        }
+        self.put(&Self::proof_key(request_id), &archived.proof)?;
+        self.track_request_id(request_id)?;
+        tracing::info!("Restored the proof of {:?} from the archive", request_id);
+        Ok(Some(archived.proof))
+    }
+
+    fn archived_proof_name(request_id: &B256) -> String {
+        format!("proofs/{}", request_id)
+    }
+
+    fn archived_request_name(l2_hash: &B256, l1_head_hash: &B256) -> String {
+        format!("requests/{}-{}", l2_hash, l1_head_hash)
+    }
+
+    // NOTE(Ethan): the schema version is checksummed but never encrypted, so that it can be read
+    // before anything is decrypted. The baseline store has none.
+    pub fn schema_version(&self) -> Result<u32> {
+        match read_sidecar(&self.schema_path)? {
+            Some(envelope) => self.open_envelope(SCHEMA_VERSION_KEY, &envelope),
+            None => Ok(0),
+        }
+    }
+
+    /// Reads the schema version back, which fails if the store cannot be read or is not migrated.
+    pub fn check_readable(&self) -> Result<()> {
+        match self.schema_version()? {
+            SCHEMA_VERSION => Ok(()),
+            version => bail!("The store is at v{}, not v{}", version, SCHEMA_VERSION),
+        }
+    }
+
+    pub fn set_schema_version(&self, version: u32) -> Result<()> {
+        let payload = bincode::serialize(&version)
+            .map_err(|e| anyhow!("Failed to serialize value: {}", e))?;
+        let body = bincode::serialize(&Sealed::new(payload))
+            .map_err(|e| anyhow!("Failed to serialize value: {}", e))?;
+        write_sidecar(&self.schema_path, &Envelope { version: SCHEMA_VERSION, body })
+            .map_err(|e| anyhow!("Failed to set schema version: {}", e))
+    }
+
+    /// Reads a sealed file next to the store, which is empty until it is first written.
+    fn fetch_sidecar<T: DeserializeOwned + Default>(&self, path: &Path, key: &[u8]) -> Result<T> {
+        match read_sidecar(path)? {
+            Some(envelope) => self.unseal(key, envelope),
+            None => Ok(T::default()),
+        }
+    }
+
+    fn put_sidecar<T: Serialize>(&self, path: &Path, key: &[u8], value: &T) -> Result<()> {
+        write_sidecar(path, &self.seal(key, value)?)
+    }
+
+    fn fetch_request_ids(&self) -> Result<Vec<B256>> {
+        self.fetch_sidecar(&self.index_path, REQUEST_IDS_KEY)
+    }
+
+    /// Returns the ids of every request known to the store, oldest first. The index can still
+    /// hold requests whose records expired, until `prune_request_ids`.
+    pub fn request_ids(&self) -> Vec<B256> {
+        self.fetch_request_ids().unwrap_or_else(|e| {
+            tracing::error!("Failed to load the request index: {:?}", e);
+            Vec::new()
+        })
+    }
+
+    pub(crate) fn track_request_id(&self, request_id: &B256) -> Result<()> {
+        let _guard = self.index_lock.lock().unwrap();
+        let mut request_ids = self.fetch_request_ids()?;
+        if request_ids.contains(request_id) {
+            return Ok(());
+        }
+        request_ids.push(*request_id);
+        self.put_sidecar(&self.index_path, REQUEST_IDS_KEY, &request_ids)
+            .map_err(|e| anyhow!("Failed to update request index: {}", e))?;
+        crate::metrics::STORE_SIZE.inc();
+        Ok(())
+    }
+
+    /// Drops the requests whose metadata and proof both expired from the index. Returns the
+    /// number of dropped requests.
+    pub fn prune_request_ids(&self) -> Result<usize> {
+        let _guard = self.index_lock.lock().unwrap();
+        let mut request_ids = self.fetch_request_ids()?;
+        let len = request_ids.len();
+        request_ids.retain(|request_id| {
+            self.get_envelope(&Self::request_key(request_id)).is_some() ||
+                self.get_envelope(&Self::proof_key(request_id)).is_some()
+        });
+        let pruned = len - request_ids.len();
+        if pruned == 0 {
+            return Ok(0);
+        }
+        self.put_sidecar(&self.index_path, REQUEST_IDS_KEY, &request_ids)
+            .map_err(|e| anyhow!("Failed to update request index: {}", e))?;
+        crate::metrics::STORE_SIZE.sub(pruned as i64);
+        tracing::info!("Pruned {} expired request(s) from the index", pruned);
+        Ok(pruned)
+    }
+
     pub fn set_request_id(
         &self,
         l2_hash: &B256,
@@ -35,21 +568,270 @@ impl ProofDB {
         request_id: &B256,
     ) -> Result<()> {
         let key = Self::build_key(l2_hash, l1_head_hash);
-        self.db.set(&key, &request_id).map_err(|e| anyhow!("Failed to set request id: {}", e))
+        self.put(&key, request_id).map_err(|e| anyhow!("Failed to set request id: {}", e))?;
+        self.track_request_id(request_id)
+    }
+
+    /// Stores the metadata of a request, which also maps its request id back to its hashes.
+    pub fn set_request_metadata(&self, metadata: &RequestMetadata) -> Result<()> {
+        let previous = self.get_request_metadata(&metadata.request_id).map(|stored| stored.status);
+        self.write_request_metadata(metadata, previous)
+    }
+
+    /// Writes the metadata of a request which was in the state `previous`, if it was stored.
+    fn write_request_metadata(
+        &self,
+        metadata: &RequestMetadata,
+        previous: Option<RequestResult>,
+    ) -> Result<()> {
+        self.put(&Self::request_key(&metadata.request_id), metadata)?;
+        crate::metrics::observe_state(previous.as_ref(), &metadata.status);
+        self.track_request_id(&metadata.request_id)
+    }
+
+    pub fn get_request_metadata(&self, request_id: &B256) -> Option<RequestMetadata> {
+        self.fetch(&Self::request_key(request_id)).unwrap_or_else(|e| {
+            tracing::error!("Failed to load the metadata of {:?}: {:?}", request_id, e);
+            None
+        })
     }
 
-    pub fn set_proof<T: ToString>(
+    pub fn update_request_status(&self, request_id: &B256, status: RequestResult) -> Result<()> {
+        let mut metadata = match self.get_request_metadata(request_id) {
+            Some(metadata) => metadata,
+            None => return Ok(()),
+        };
+        if metadata.status == status {
+            return Ok(());
+        }
+        metadata.updated_at = crate::utils::now_secs();
+        let previous = std::mem::replace(&mut metadata.status, status);
+        self.write_request_metadata(&metadata, Some(previous))?;
+        if metadata.status == RequestResult::Completed {
+            self.record_proving_secs(metadata.updated_at.saturating_sub(metadata.created_at));
+        }
+        Ok(())
+    }
+
+    /// Marks the request of the hashes cancelled, so that it is no longer polled.
+    pub fn cancel_request(
         &self,
-        request_id: &T,
-        proof: &SP1ProofWithPublicValues,
+        request_id: &B256,
+        l2_hash: &B256,
+        l1_head_hash: &B256,
     ) -> Result<()> {
-        let req_id_key = Self::convert_req_id_as_key(request_id);
-        self.db.set(&req_id_key, &proof)
+        if self.get_request_metadata(request_id).is_some() {
+            return self.update_request_status(request_id, RequestResult::Cancelled);
+        }
+        // NOTE(Ethan): a request submitted before the metadata was stored has none, so it is
+        // created here to record the cancellation.
+        let mut metadata = RequestMetadata::new(*request_id, *l2_hash, *l1_head_hash, None);
+        metadata.status = RequestResult::Cancelled;
+        self.set_request_metadata(&metadata)
+    }
+
+    /// Returns the average time from request to completion over the recent completed requests.
+    pub fn estimated_proving_secs(&self) -> u64 {
+        let mut proving_secs = self.proving_secs.lock().unwrap();
+        let durations = proving_secs.get_or_insert_with(|| self.recent_proving_secs());
+        if durations.is_empty() {
+            return DEFAULT_PROVING_SECS;
+        }
+        durations.iter().sum::<u64>() / durations.len() as u64
+    }
+
+    /// Reads the durations of the recent completed requests from the store, oldest first.
+    fn recent_proving_secs(&self) -> VecDeque<u64> {
+        let mut durations: VecDeque<u64> = self
+            .request_ids()
+            .iter()
+            .rev()
+            .filter_map(|request_id| self.get_request_metadata(request_id))
+            .filter(|metadata| metadata.status == RequestResult::Completed)
+            .take(PROVING_SECS_SAMPLES)
+            .map(|metadata| metadata.updated_at.saturating_sub(metadata.created_at))
+            .collect();
+        durations.make_contiguous().reverse();
+        durations
+    }
+
+    fn record_proving_secs(&self, secs: u64) {
+        // NOTE(Ethan): before the first estimate, the completion is read from the store instead.
+        if let Some(durations) = self.proving_secs.lock().unwrap().as_mut() {
+            if durations.len() == PROVING_SECS_SAMPLES {
+                durations.pop_front();
+            }
+            durations.push_back(secs);
+        }
+    }
+
+    /// Returns up to `limit` requests matching `filter`, newest first, starting after the request
+    /// `after`. The second value is the cursor of the next page, if there is one.
+    pub fn list_requests<F: Fn(&RequestMetadata) -> bool>(
+        &self,
+        filter: F,
+        after: Option<&B256>,
+        limit: usize,
+    ) -> (Vec<RequestMetadata>, Option<B256>) {
+        let request_ids = self.request_ids();
+        let start = match after {
+            Some(after) => match request_ids.iter().rev().position(|id| id == after) {
+                Some(position) => position + 1,
+                None => return (Vec::new(), None),
+            },
+            None => 0,
+        };
+
+        let mut matched = request_ids
+            .iter()
+            .rev()
+            .skip(start)
+            .filter_map(|request_id| self.get_request_metadata(request_id))
+            .filter(|metadata| filter(metadata));
+        let requests: Vec<_> = matched.by_ref().take(limit).collect();
+        let next_cursor = match matched.next() {
+            Some(_) => requests.last().map(|metadata| metadata.request_id),
+            None => None,
+        };
+
+        (requests, next_cursor)
+    }
+
+    pub fn set_proof(&self, request_id: &B256, proof: &SP1ProofWithPublicValues) -> Result<()> {
+        self.set_stored_proof(request_id, &StoredProof::new(proof)?)
+    }
+
+    fn set_stored_proof(&self, request_id: &B256, proof: &StoredProof) -> Result<()> {
+        self.put(&Self::proof_key(request_id), proof)?;
+        self.proof_cache.invalidate(request_id);
+        self.release(request_id)?;
+        self.track_request_id(request_id)?;
+
+        // NOTE(Ethan): the local store stays the source of truth, so a failure of the archive
+        // does not fail the write.
+        if let Err(e) = self.archive_proof(request_id, proof) {
+            tracing::error!("Failed to archive the proof of {:?}: {:?}", request_id, e);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_stored_proof(&self, request_id: &B256) -> Result<Option<StoredProof>> {
+        if self.is_quarantined(request_id) {
+            return Ok(None);
+        }
+        if let Some(proof) = self.fetch(&Self::proof_key(request_id))? {
+            return Ok(Some(proof));
+        }
+
+        // Upgrade a proof written before the schema versioning on its first read.
+        match self.get_legacy_proof(request_id) {
+            Some(proof) => {
+                let proof = StoredProof::new(&proof)?;
+                self.set_stored_proof(request_id, &proof)?;
+                tracing::info!("Upgraded a legacy proof record: {:?}", request_id);
+                Ok(Some(proof))
+            }
+            None => self.restore_proof(request_id),
+        }
     }
 
+    /// Returns the proof of `request_id` as it was written before the schema versioning.
+    pub(crate) fn get_legacy_proof(&self, request_id: &B256) -> Option<SP1ProofWithPublicValues> {
+        self.db.get(&Self::convert_req_id_as_key(request_id))
+    }
+
+    /// Returns the request id of the hashes from the local store.
     pub fn get_request_id(&self, l2_hash: &B256, l1_head_hash: &B256) -> Option<B256> {
         let key = Self::build_key(l2_hash, l1_head_hash);
-        self.db.get(&key)
+        // NOTE(Ethan): a mapping of a v0 store is kept raw, as it cannot be found to be sealed.
+        let request_id = self.fetch(&key).unwrap_or_else(|e| {
+            tracing::error!("Failed to load the request id of {:?}: {:?}", key, e);
+            None
+        });
+        let request_id = request_id.or_else(|| self.get_raw(&key))?;
+        (!self.request_history(l2_hash, l1_head_hash).contains(&request_id)).then_some(request_id)
+    }
+
+    /// Returns the request id of the hashes like `get_request_id`, falling back to the archive
+    /// where another proxy sharing it may have completed the request. Only the paths which fetch
+    /// a proof read through, so that a local miss elsewhere stays local.
+    pub fn find_request_id(&self, l2_hash: &B256, l1_head_hash: &B256) -> Option<B256> {
+        if let Some(request_id) = self.get_request_id(l2_hash, l1_head_hash) {
+            return Some(request_id);
+        }
+        let archive = self.archive.as_ref()?;
+
+        let name = Self::archived_request_name(l2_hash, l1_head_hash);
+        let request_id: B256 = match self.fetch_archived(archive, &name) {
+            Ok(request_id) => request_id?,
+            Err(e) => {
+                tracing::error!("Failed to read {} from the archive: {:?}", name, e);
+                return None;
+            }
+        };
+        if self.request_history(l2_hash, l1_head_hash).contains(&request_id) {
+            return None;
+        }
+        let key = Self::build_key(l2_hash, l1_head_hash);
+        if let Err(e) = self.put(&key, &request_id).and_then(|_| self.track_request_id(&request_id))
+        {
+            tracing::error!("Failed to restore the request id {:?}: {:?}", request_id, e);
+        }
+        Some(request_id)
+    }
+
+    fn history_key(l2_hash: &B256, l1_head_hash: &B256) -> Vec<u8> {
+        [HISTORY_KEY_PREFIX, &Self::build_key(l2_hash, l1_head_hash)].concat()
+    }
+
+    /// Returns the ids of the discarded requests of the hashes, oldest first.
+    pub fn request_history(&self, l2_hash: &B256, l1_head_hash: &B256) -> Vec<B256> {
+        self.fetch_logged(&Self::history_key(l2_hash, l1_head_hash), "request history")
+    }
+
+    /// Discards the request of the hashes, so that the next `requestProve` submits a fresh one.
+    /// The discarded request is kept in the history, and its metadata and proof can still be
+    /// found by its id. Returns the id of the discarded request, if there was one.
+    pub fn discard_request(&self, l2_hash: &B256, l1_head_hash: &B256) -> Result<Option<B256>> {
+        let request_id = match self.get_request_id(l2_hash, l1_head_hash) {
+            Some(request_id) => request_id,
+            None => return Ok(None),
+        };
+        let _guard = self.index_lock.lock().unwrap();
+        let history_key = Self::history_key(l2_hash, l1_head_hash);
+        let mut history: Vec<B256> = self.fetch_or_default(&history_key)?;
+        history.push(request_id);
+        self.put(&history_key, &history)
+            .map_err(|e| anyhow!("Failed to update request history: {}", e))?;
+        self.proof_cache.invalidate(&request_id);
+        Ok(Some(request_id))
+    }
+
+    fn idempotency_key(key: &str) -> Vec<u8> {
+        [IDEMPOTENCY_KEY_PREFIX, key.as_bytes()].concat()
+    }
+
+    /// Returns the hashes which `idempotency_key` was first used with.
+    pub fn get_idempotency_key(&self, idempotency_key: &str) -> Option<(B256, B256)> {
+        self.fetch(&Self::idempotency_key(idempotency_key)).unwrap_or_else(|e| {
+            tracing::error!("Failed to load the idempotency key {:?}: {:?}", idempotency_key, e);
+            None
+        })
+    }
+
+    pub fn set_idempotency_key(
+        &self,
+        idempotency_key: &str,
+        l2_hash: &B256,
+        l1_head_hash: &B256,
+    ) -> Result<()> {
+        self.put(&Self::idempotency_key(idempotency_key), &(*l2_hash, *l1_head_hash))
+            .map_err(|e| anyhow!("Failed to set idempotency key: {}", e))
+    }
+
+    pub fn get_hashes_by_request_id(&self, request_id: &B256) -> Option<(B256, B256)> {
+        self.get_request_metadata(request_id)
+            .map(|metadata| (metadata.l2_hash, metadata.l1_head_hash))
     }
 
     pub fn get_proof(
@@ -57,13 +839,312 @@ impl ProofDB {
         l2_hash: &B256,
         l1_head_hash: &B256,
     ) -> Option<SP1ProofWithPublicValues> {
-        let request_id = self.get_request_id(l2_hash, l1_head_hash);
+        let request_id = self.find_request_id(l2_hash, l1_head_hash);
         request_id.as_ref()?;
         self.get_proof_by_id(&request_id.unwrap())
     }
 
     pub fn get_proof_by_id(&self, request_id: &B256) -> Option<SP1ProofWithPublicValues> {
-        let req_id_key = Self::convert_req_id_as_key(&request_id);
-        self.db.get(&req_id_key)
+        let decoded = match self.get_stored_proof(request_id) {
+            Ok(proof) => proof?.decode(),
+            Err(e) => Err(e),
+        };
+        match decoded {
+            Ok(proof) => Some(proof),
+            Err(e) => {
+                tracing::error!("Failed to load the proof of {:?}: {:?}", request_id, e);
+                None
+            }
+        }
+    }
+
+    /// Returns the completed `ProofResult` of `request_id`, from the cache if possible.
+    pub fn get_proof_result(&self, request_id: &B256) -> Option<ProofResult> {
+        if let Some(result) = self.proof_cache.get(request_id) {
+            return Some(result);
+        }
+        let proof = self.get_proof_by_id(request_id)?;
+        let result = ProofResult::new(request_id, RequestResult::Completed, proof);
+
+        // NOTE(Ethan): `quarantine` invalidates the cache under the quarantine lock, so the
+        // quarantine is checked under it too. Otherwise a proof quarantined while it is read here
+        // would be cached again right after its invalidation.
+        let cached = self.with_quarantine(|quarantined| {
+            if quarantined.iter().any(|entry| entry.request_id == *request_id) {
+                return Ok(None);
+            }
+            self.proof_cache.put(*request_id, result.clone());
+            Ok(Some(result))
+        });
+        cached.unwrap_or_else(|e| {
+            tracing::error!("Failed to load the quarantine: {:?}", e);
+            None
+        })
+    }
+
+    /// Runs `f` on the quarantine, which is read from its file once and then kept in memory, so
+    /// that a proof read does not decrypt it.
+    fn with_quarantine<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<QuarantinedProof>) -> Result<T>,
+    ) -> Result<T> {
+        let mut quarantine = self.quarantine.lock().unwrap();
+        let quarantined = match quarantine.as_mut() {
+            Some(quarantined) => quarantined,
+            None => quarantine.insert(self.fetch_sidecar(&self.quarantine_path, QUARANTINE_KEY)?),
+        };
+        f(quarantined)
+    }
+
+    pub fn quarantined_proofs(&self) -> Vec<QuarantinedProof> {
+        self.with_quarantine(|quarantined| Ok(quarantined.clone())).unwrap_or_else(|e| {
+            tracing::error!("Failed to load the quarantine: {:?}", e);
+            Vec::new()
+        })
+    }
+
+    pub fn is_quarantined(&self, request_id: &B256) -> bool {
+        self.with_quarantine(|quarantined| {
+            Ok(quarantined.iter().any(|entry| entry.request_id == *request_id))
+        })
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to load the quarantine: {:?}", e);
+            false
+        })
+    }
+
+    /// Stops serving the proof of `request_id`. The proof is fetched from the SP1 network again on
+    /// the next status query, which releases it from the quarantine.
+    pub fn quarantine(&self, request_id: &B256, reason: String) -> Result<()> {
+        self.with_quarantine(|quarantined| {
+            if quarantined.iter().any(|entry| entry.request_id == *request_id) {
+                return Ok(());
+            }
+            let mut updated = quarantined.clone();
+            updated.push(QuarantinedProof {
+                request_id: *request_id,
+                reason: reason.clone(),
+                quarantined_at: crate::utils::now_secs(),
+            });
+            self.put_sidecar(&self.quarantine_path, QUARANTINE_KEY, &updated)
+                .map_err(|e| anyhow!("Failed to update quarantine: {}", e))?;
+            *quarantined = updated;
+            tracing::warn!("Quarantined the proof of {:?}: {}", request_id, reason);
+            self.proof_cache.invalidate(request_id);
+            crate::metrics::QUARANTINED_PROOFS.inc();
+            Ok(())
+        })
+    }
+
+    fn release(&self, request_id: &B256) -> Result<()> {
+        self.with_quarantine(|quarantined| {
+            if !quarantined.iter().any(|entry| entry.request_id == *request_id) {
+                return Ok(());
+            }
+            let mut updated = quarantined.clone();
+            updated.retain(|entry| entry.request_id != *request_id);
+            self.put_sidecar(&self.quarantine_path, QUARANTINE_KEY, &updated)
+                .map_err(|e| anyhow!("Failed to update quarantine: {}", e))?;
+            *quarantined = updated;
+            tracing::info!("Released the proof of {:?} from quarantine", request_id);
+            Ok(())
+        })
+    }
+
+    fn fetch_callbacks(&self) -> Result<Vec<PendingCallback>> {
+        self.fetch_sidecar(&self.outbox_path, CALLBACKS_KEY)
+    }
+
+    fn set_callbacks(&self, callbacks: &[PendingCallback]) -> Result<()> {
+        self.put_sidecar(&self.outbox_path, CALLBACKS_KEY, callbacks)
+            .map_err(|e| anyhow!("Failed to update callbacks: {}", e))
     }
+
+    /// Returns the outbox of callbacks which are not delivered yet.
+    pub fn pending_callbacks(&self) -> Vec<PendingCallback> {
+        self.fetch_callbacks().unwrap_or_else(|e| {
+            tracing::error!("Failed to load the callback outbox: {:?}", e);
+            Vec::new()
+        })
+    }
+
+    fn update_callbacks(&self, f: impl FnOnce(&mut Vec<PendingCallback>)) -> Result<()> {
+        let _guard = self.index_lock.lock().unwrap();
+        let mut callbacks = self.fetch_callbacks()?;
+        f(&mut callbacks);
+        self.set_callbacks(&callbacks)
+    }
+
+    /// Adds a callback to the outbox, replacing the one of the same hashes and url if any. A
+    /// callback without a `request_id` waits until `attach_callbacks` or `fail_callbacks`.
+    pub fn add_callback(&self, mut callback: PendingCallback) -> Result<()> {
+        self.update_callbacks(|callbacks| {
+            callbacks.retain(|entry| !entry.is_same(&callback));
+            while callbacks.iter().any(|entry| entry.id == callback.id) {
+                callback.id += 1;
+            }
+            callbacks.push(callback);
+        })
+    }
+
+    /// Reports the request which the hashes reached the SP1 network with to their waiting
+    /// callbacks.
+    pub fn attach_callbacks(
+        &self,
+        l2_hash: &B256,
+        l1_head_hash: &B256,
+        request_id: &B256,
+    ) -> Result<()> {
+        self.update_callbacks(|callbacks| {
+            for entry in callbacks.iter_mut() {
+                if entry.is_for(l2_hash, l1_head_hash) && entry.request_id.is_none() {
+                    entry.request_id = Some(*request_id);
+                }
+            }
+        })
+    }
+
+    /// Reports a failure to the waiting callbacks of the hashes, whose request did not reach the
+    /// SP1 network.
+    pub fn fail_callbacks(&self, l2_hash: &B256, l1_head_hash: &B256) -> Result<()> {
+        self.update_callbacks(|callbacks| {
+            for entry in callbacks.iter_mut() {
+                if entry.is_for(l2_hash, l1_head_hash) && entry.request_id.is_none() {
+                    entry.failed = true;
+                }
+            }
+        })
+    }
+
+    /// Updates `callback` unless it was replaced in the meantime.
+    pub fn update_callback(&self, callback: &PendingCallback) -> Result<()> {
+        self.update_callbacks(|callbacks| {
+            if let Some(entry) = callbacks.iter_mut().find(|entry| entry.id == callback.id) {
+                *entry = callback.clone();
+            }
+        })
+    }
+
+    /// Removes `callback`, but not a callback which replaced it in the meantime.
+    pub fn remove_callback(&self, callback: &PendingCallback) -> Result<()> {
+        self.update_callbacks(|callbacks| callbacks.retain(|entry| entry.id != callback.id))
+    }
+
+    /// Opens a record whatever its schema version is, if there is one.
+    fn open_any<T: DeserializeOwned>(&self, key: &[u8]) -> Result<Option<T>> {
+        match self.get_envelope(key) {
+            Some(envelope) => self.open_envelope(key, &envelope).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the keys of the sealed records in `FileDB`, whatever their schema version.
+    fn sealed_keys(&self) -> Result<Vec<Vec<u8>>> {
+        // NOTE(Ethan): the keys of the mappings are found through the metadata, so every key is
+        // collected before anything is rewritten.
+        let mut keys = Vec::new();
+        let mut hashes = HashSet::new();
+        let request_ids: Vec<B256> = match read_sidecar(&self.index_path)? {
+            Some(envelope) => self.open_envelope(REQUEST_IDS_KEY, &envelope)?,
+            None => Vec::new(),
+        };
+        for request_id in request_ids {
+            keys.push(Self::proof_key(&request_id));
+            keys.push(Self::request_key(&request_id));
+            let metadata: RequestMetadata = match self.open_any(&Self::request_key(&request_id))? {
+                Some(metadata) => metadata,
+                None => continue,
+            };
+            if let Some(idempotency_key) = &metadata.idempotency_key {
+                keys.push(Self::idempotency_key(idempotency_key));
+            }
+            if hashes.insert((metadata.l2_hash, metadata.l1_head_hash)) {
+                keys.push(Self::build_key(&metadata.l2_hash, &metadata.l1_head_hash));
+                keys.push(Self::history_key(&metadata.l2_hash, &metadata.l1_head_hash));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Returns `envelope` encrypted with the active key, or `None` if it already is.
+    fn reseal(&self, key: &[u8], envelope: &Envelope) -> Result<Option<Envelope>> {
+        let key_ring = match &self.key_ring {
+            Some(key_ring) => key_ring,
+            None => bail!("No key ring is given"),
+        };
+        let sealed: Sealed = bincode::deserialize(&envelope.body)
+            .map_err(|e| anyhow!("Failed to deserialize value: {}", e))?;
+        if sealed.key_id.as_deref() == Some(key_ring.active_key_id()) {
+            return Ok(None);
+        }
+        let payload = sealed.open(Some(key_ring), key)?;
+        let body = bincode::serialize(&Sealed::from_ciphertext(key_ring.encrypt(&payload, key)?))
+            .map_err(|e| anyhow!("Failed to serialize value: {}", e))?;
+        Ok(Some(Envelope { version: SCHEMA_VERSION, body }))
+    }
+
+    /// Rewrites every record which is not encrypted with the active key, e.g. after a new key is
+    /// put first in the key ring. Returns the number of records which were (or, on a dry run,
+    /// would be) rewritten.
+    pub fn rotate_encryption(&self, dry_run: bool) -> Result<usize> {
+        if self.key_ring.is_none() {
+            bail!("No key ring is given");
+        }
+
+        let mut affected = 0;
+        for key in self.sealed_keys()? {
+            let envelope = match self.get_envelope(&key) {
+                Some(envelope) => envelope,
+                None => continue,
+            };
+            if let Some(envelope) = self.reseal(&key, &envelope)? {
+                affected += 1;
+                if !dry_run {
+                    self.set_envelope(&key, &envelope)?;
+                }
+            }
+        }
+
+        let _guard = self.index_lock.lock().unwrap();
+        let sidecars = [
+            (&self.index_path, REQUEST_IDS_KEY),
+            (&self.quarantine_path, QUARANTINE_KEY),
+            (&self.outbox_path, CALLBACKS_KEY),
+        ];
+        for (path, key) in sidecars {
+            let envelope = match read_sidecar(path)? {
+                Some(envelope) => envelope,
+                None => continue,
+            };
+            if let Some(envelope) = self.reseal(key, &envelope)? {
+                affected += 1;
+                if !dry_run {
+                    write_sidecar(path, &envelope)?;
+                }
+            }
+        }
+
+        Ok(affected)
+    }
+}
+
+fn read_sidecar(path: &Path) -> Result<Option<Envelope>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => bail!("Failed to read {}: {}", path.display(), e),
+    };
+    bincode::deserialize(&data).map(Some).map_err(|e| anyhow!("Failed to deserialize value: {}", e))
+}
+
+/// Replaces the file at `path` with `envelope` through a temporary file, so that it is never
+/// left half written.
+fn write_sidecar(path: &Path, envelope: &Envelope) -> Result<()> {
+    let data =
+        bincode::serialize(envelope).map_err(|e| anyhow!("Failed to serialize value: {}", e))?;
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, data)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
 }
@@ -0,0 +1,140 @@
+use alloy_primitives::B256;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    sync::{Condvar, Mutex},
+};
+
+use crate::{
+    metrics::QUEUE_DEPTH,
+    types::{RequestOptions, RequestResult},
+};
+
+/// A request whose witness is ready, waiting to be submitted to the SP1 network.
+pub struct QueuedRequest {
+    pub user_req_id: String,
+    pub l2_hash: B256,
+    pub l1_head_hash: B256,
+    pub caller: Option<String>,
+    pub witness_buf: Vec<Vec<u8>>,
+    pub options: RequestOptions,
+    seq: u64,
+}
+
+impl QueuedRequest {
+    pub fn new(
+        user_req_id: String,
+        l2_hash: B256,
+        l1_head_hash: B256,
+        caller: Option<String>,
+        witness_buf: Vec<Vec<u8>>,
+        options: RequestOptions,
+    ) -> Self {
+        Self { user_req_id, l2_hash, l1_head_hash, caller, witness_buf, options, seq: 0 }
+    }
+}
+
+// NOTE(Ethan): `BinaryHeap` pops the greatest first, so the order is the higher priority, then
+// the earlier deadline (any deadline before none), then the earlier arrival.
+impl Ord for QueuedRequest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let deadline = |request: &Self| request.options.deadline.unwrap_or(u64::MAX);
+        self.options
+            .priority
+            .unwrap_or_default()
+            .cmp(&other.options.priority.unwrap_or_default())
+            .then_with(|| deadline(other).cmp(&deadline(self)))
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for QueuedRequest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedRequest {}
+
+#[derive(Default)]
+struct QueueState {
+    requests: BinaryHeap<QueuedRequest>,
+    /// The status of the requests which have not reached the SP1 network yet, by `user_req_id`.
+    statuses: HashMap<String, RequestResult>,
+    next_seq: u64,
+}
+
+/// The requests which are not submitted to the SP1 network yet, either waiting for their witness
+/// or for a submitter. They are `Processing` meanwhile, and a failed or cancelled one is kept as
+/// `Failed` or `Cancelled` until the next `requestProve`.
+#[derive(Default)]
+pub struct SubmissionQueue {
+    state: Mutex<QueueState>,
+    available: Condvar,
+}
+
+impl SubmissionQueue {
+    pub fn status(&self, user_req_id: &str) -> Option<RequestResult> {
+        self.state.lock().unwrap().statuses.get(user_req_id).cloned()
+    }
+
+    pub fn set_status(&self, user_req_id: &str, status: Option<RequestResult>) {
+        let mut state = self.state.lock().unwrap();
+        match status {
+            Some(status) => state.statuses.insert(user_req_id.to_string(), status),
+            None => state.statuses.remove(user_req_id),
+        };
+    }
+
+    pub fn push(&self, mut request: QueuedRequest) {
+        let mut state = self.state.lock().unwrap();
+        request.seq = state.next_seq;
+        state.next_seq += 1;
+        state.statuses.insert(request.user_req_id.clone(), RequestResult::Processing);
+        state.requests.push(request);
+        QUEUE_DEPTH.set(state.requests.len() as i64);
+        self.available.notify_one();
+    }
+
+    /// Blocks until a request is queued, and returns the most urgent one. It stays `Processing`
+    /// until the submitter sets its status.
+    pub fn pop(&self) -> QueuedRequest {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(request) = state.requests.pop() {
+                QUEUE_DEPTH.set(state.requests.len() as i64);
+                return request;
+            }
+            state = self.available.wait(state).unwrap();
+        }
+    }
+
+    /// Cancels a request which is `Processing`, removing it from the queue if it is waiting for a
+    /// submitter. A request waiting for its witness, or popped by a submitter which has not taken
+    /// its lock yet, is dropped by whoever finds it `Cancelled`. Returns whether it was cancelled.
+    pub fn cancel(&self, user_req_id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.statuses.get(user_req_id) != Some(&RequestResult::Processing) {
+            return false;
+        }
+        state.requests.retain(|request| request.user_req_id != user_req_id);
+        QUEUE_DEPTH.set(state.requests.len() as i64);
+        state.statuses.insert(user_req_id.to_string(), RequestResult::Cancelled);
+        true
+    }
+
+    /// Returns the number of requests waiting for a submitter.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
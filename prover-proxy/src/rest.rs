@@ -0,0 +1,229 @@
+use alloy_primitives::B256;
+use jsonrpc_core::{Error as JsonError, ErrorCode as JsonErrorCode, Result as JsonResult};
+use jsonrpc_http_server::hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    http::request::Parts,
+    Body, Method, Request, Response, StatusCode,
+};
+use kroma_zkvm_common::types::preprocessing;
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, time::Instant};
+
+use crate::{
+    errors::ProverErrorCode,
+    interface::{CallerMeta, Rpc, RpcImpl},
+    metrics,
+    middleware::read_body_limited,
+    types::{RequestOptions, WitnessInput},
+    MAX_REQUEST_BODY_SIZE,
+};
+
+/// The OpenAPI document of the REST API, served at `/openapi.json`.
+pub const OPENAPI_DOCUMENT: &str = include_str!("openapi.json");
+
+/// The body of `POST /v1/proofs/{l2_hash}/{l1_head_hash}`, which are the trailing parameters of
+/// `requestProve`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProveRequestBody {
+    #[serde(default)]
+    pub witness: Option<WitnessInput>,
+    #[serde(default)]
+    pub options: Option<RequestOptions>,
+}
+
+/// An endpoint of the REST API, each of which calls the JSON-RPC method of the same name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Route {
+    OpenApi,
+    Spec,
+    RequestProve { l2_hash: String, l1_head_hash: String },
+    GetProof { l2_hash: String, l1_head_hash: String },
+    GetStatus { l2_hash: String, l1_head_hash: String },
+    CancelRequest { l2_hash: String, l1_head_hash: String },
+    GetProofByRequestId { request_id: String },
+}
+
+impl Route {
+    /// Returns whether `path` belongs to the REST API rather than JSON-RPC.
+    pub fn is_rest_path(path: &str) -> bool {
+        path == "/openapi.json" || path == "/v1" || path.starts_with("/v1/")
+    }
+
+    /// Returns the route of a request, or the status to answer it with if there is none or its
+    /// hashes are invalid.
+    pub fn parse(method: &Method, path: &str) -> Result<Self, StatusCode> {
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').skip(1).collect();
+        // NOTE(Ethan): the errors of invalid hashes do not carry their code, so they are rejected
+        // here rather than answered by the method.
+        let hashes = |l2_hash: &str, l1_head_hash: &str| match preprocessing(l2_hash, l1_head_hash)
+        {
+            Ok(_) => Ok((l2_hash.to_string(), l1_head_hash.to_string())),
+            Err(_) => Err(StatusCode::BAD_REQUEST),
+        };
+        let route = match segments.as_slice() {
+            ["openapi.json"] => (Method::GET, Self::OpenApi),
+            ["v1", "spec"] => (Method::GET, Self::Spec),
+            ["v1", "proofs", l2_hash, l1_head_hash] => {
+                let (l2_hash, l1_head_hash) = hashes(l2_hash, l1_head_hash)?;
+                match *method {
+                    Method::POST => (Method::POST, Self::RequestProve { l2_hash, l1_head_hash }),
+                    Method::DELETE => {
+                        (Method::DELETE, Self::CancelRequest { l2_hash, l1_head_hash })
+                    }
+                    _ => (Method::GET, Self::GetProof { l2_hash, l1_head_hash }),
+                }
+            }
+            ["v1", "proofs", l2_hash, l1_head_hash, "status"] => {
+                let (l2_hash, l1_head_hash) = hashes(l2_hash, l1_head_hash)?;
+                (Method::GET, Self::GetStatus { l2_hash, l1_head_hash })
+            }
+            ["v1", "requests", request_id] => {
+                B256::from_str(request_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+                (Method::GET, Self::GetProofByRequestId { request_id: request_id.to_string() })
+            }
+            _ => return Err(StatusCode::NOT_FOUND),
+        };
+        match route {
+            (expected, route) if expected == *method => Ok(route),
+            _ => Err(StatusCode::METHOD_NOT_ALLOWED),
+        }
+    }
+
+    /// Returns the name of the JSON-RPC method of the route.
+    pub fn method(&self) -> &'static str {
+        match self {
+            Self::OpenApi => "openapi",
+            Self::Spec => "spec",
+            Self::RequestProve { .. } => "requestProve",
+            Self::GetProof { .. } => "getProof",
+            Self::GetStatus { .. } => "getStatus",
+            Self::CancelRequest { .. } => "cancelRequest",
+            Self::GetProofByRequestId { .. } => "getProofByRequestId",
+        }
+    }
+
+    fn call(self, rpc_impl: &RpcImpl, meta: CallerMeta, body: &[u8]) -> JsonResult<Vec<u8>> {
+        let json = match self {
+            Self::OpenApi => return Ok(OPENAPI_DOCUMENT.as_bytes().to_vec()),
+            Self::Spec => to_json(rpc_impl.spec()?),
+            Self::RequestProve { l2_hash, l1_head_hash } => {
+                let body: ProveRequestBody = if body.is_empty() {
+                    ProveRequestBody::default()
+                } else {
+                    serde_json::from_slice(body)
+                        .map_err(|e| JsonError::invalid_params(e.to_string()))?
+                };
+                to_json(rpc_impl.request_prove(
+                    meta,
+                    l2_hash,
+                    l1_head_hash,
+                    body.witness,
+                    body.options,
+                )?)
+            }
+            Self::GetProof { l2_hash, l1_head_hash } => {
+                to_json(rpc_impl.get_proof(l2_hash, l1_head_hash)?)
+            }
+            Self::GetStatus { l2_hash, l1_head_hash } => {
+                to_json(rpc_impl.get_status(l2_hash, l1_head_hash)?)
+            }
+            Self::CancelRequest { l2_hash, l1_head_hash } => {
+                to_json(rpc_impl.cancel_request(l2_hash, l1_head_hash)?)
+            }
+            Self::GetProofByRequestId { request_id } => {
+                to_json(rpc_impl.get_proof_by_request_id(request_id)?)
+            }
+        };
+        Ok(json)
+    }
+}
+
+fn to_json<T: Serialize>(value: T) -> Vec<u8> {
+    serde_json::to_vec(&value).expect("results are serializable")
+}
+
+/// Maps the error of a JSON-RPC method to the status of the REST API.
+pub fn status_of(error: &JsonError) -> StatusCode {
+    if error.code == JsonErrorCode::InvalidParams {
+        return StatusCode::BAD_REQUEST;
+    }
+    let code = error.data.as_ref().and_then(|data| data.as_i64());
+    let is = |prover_code: ProverErrorCode| code == Some(prover_code.code());
+    if is(ProverErrorCode::InvalidInputHash) || is(ProverErrorCode::InvalidWitness) {
+        StatusCode::BAD_REQUEST
+    } else if is(ProverErrorCode::SP1NetworkError) {
+        StatusCode::BAD_GATEWAY
+    } else if is(ProverErrorCode::RequestNotFound) {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+pub(crate) fn json_response(status: StatusCode, body: Vec<u8>) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn error_response(status: StatusCode, error: JsonError) -> Response<Body> {
+    tracing::error!("REST request failed with {}: {:?}", status, error);
+    json_response(status, to_json(error))
+}
+
+/// The REST API, which mirrors the JSON-RPC methods on the same handlers.
+#[derive(Clone)]
+pub struct RestApi {
+    rpc_impl: RpcImpl,
+}
+
+impl RestApi {
+    pub fn new(rpc_impl: RpcImpl) -> Self {
+        Self { rpc_impl }
+    }
+
+    pub async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let (parts, body) = request.into_parts();
+        let body = match read_body_limited(body, MAX_REQUEST_BODY_SIZE).await {
+            Ok(body) => body,
+            Err(e) => {
+                let error = JsonError::invalid_request();
+                tracing::error!("Failed to read the body of a REST request: {:?}", e);
+                return error_response(StatusCode::PAYLOAD_TOO_LARGE, error);
+            }
+        };
+        self.handle_body(parts, body).await
+    }
+
+    /// Answers a request whose body is already read, e.g. decompressed by `ProxyMiddleware`.
+    pub async fn handle_body(&self, parts: Parts, body: Vec<u8>) -> Response<Body> {
+        let route = match Route::parse(&parts.method, parts.uri.path()) {
+            Ok(route) => route,
+            Err(status) => {
+                let message = status.canonical_reason().unwrap_or_default().to_string();
+                let error = JsonError { code: JsonErrorCode::InvalidRequest, message, data: None };
+                return error_response(status, error);
+            }
+        };
+        tracing::info!("Received REST request: {} {:?}", parts.method, route);
+
+        let meta = CallerMeta::from_headers(&parts.headers);
+        // NOTE(Ethan): the handlers block on the SP1 network, so they do not run on the server.
+        let (method, started) = (route.method(), Instant::now());
+        let rpc_impl = self.rpc_impl.clone();
+        let result =
+            match tokio::task::spawn_blocking(move || route.call(&rpc_impl, meta, &body)).await {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::error!("A REST handler panicked: {:?}", e);
+                    Err(JsonError::internal_error())
+                }
+            };
+        metrics::observe_rpc(method, started, result.is_ok());
+        match result {
+            Ok(body) => json_response(StatusCode::OK, body),
+            Err(error) => error_response(status_of(&error), error),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use alloy_primitives::B256;
+use sp1_sdk::ProverClient;
+use std::{collections::HashSet, sync::Arc, thread, time::Duration};
+
+use crate::{metrics, proof_db::ProofDB, VERIFYING_KEY};
+
+/// Which stored proofs a scrub verifies against `VERIFYING_KEY`, on top of their checksums.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// Verifies this many proofs, starting at an offset into the request index.
+    Batch(usize),
+    All,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ScrubReport {
+    pub checked: usize,
+    pub verified: usize,
+    pub quarantined: Vec<B256>,
+}
+
+/// Checks every stored proof which is not quarantined yet against its checksum, verifies the ones
+/// chosen by `verification` and quarantines the ones which fail so that they are never served. A
+/// batch starts at `offset` into the request index and wraps around.
+pub fn scrub(proof_db: &ProofDB, verification: Verification, offset: usize) -> ScrubReport {
+    let quarantined: HashSet<B256> =
+        proof_db.quarantined_proofs().iter().map(|entry| entry.request_id).collect();
+    let request_ids: Vec<B256> =
+        proof_db.request_ids().into_iter().filter(|id| !quarantined.contains(id)).collect();
+    let to_verify: HashSet<B256> = match verification {
+        Verification::All => request_ids.iter().copied().collect(),
+        Verification::Batch(size) => request_ids
+            .iter()
+            .cycle()
+            .skip(offset % request_ids.len().max(1))
+            .take(size.min(request_ids.len()))
+            .copied()
+            .collect(),
+    };
+    // NOTE(Ethan): `VERIFYING_KEY` has to be set up before `ProverClient::from_env()` is called,
+    // and neither is needed if nothing is verified.
+    let prover = (!to_verify.is_empty()).then(|| (&*VERIFYING_KEY, ProverClient::from_env()));
+
+    let mut report = ScrubReport::default();
+    for request_id in request_ids {
+        let proof = match proof_db.get_stored_proof(&request_id) {
+            Ok(Some(proof)) => proof.decode(),
+            Ok(None) => continue,
+            Err(e) => Err(e),
+        };
+        let result = match (proof, &prover) {
+            (Ok(proof), Some((vkey, prover))) if to_verify.contains(&request_id) => {
+                report.verified += 1;
+                prover.verify(&proof, vkey).map_err(|e| anyhow::anyhow!("Invalid proof: {}", e))
+            }
+            (proof, _) => proof.map(|_| ()),
+        };
+        report.checked += 1;
+        metrics::SCRUBBED_PROOFS.inc();
+
+        if let Err(e) = result {
+            match proof_db.quarantine(&request_id, e.to_string()) {
+                Ok(()) => report.quarantined.push(request_id),
+                Err(e) => tracing::error!("Failed to quarantine {:?}: {:?}", request_id, e),
+            }
+        }
+    }
+
+    tracing::info!(
+        "Scrubbed the proof store: {} checked, {} verified, {} quarantined",
+        report.checked,
+        report.verified,
+        report.quarantined.len()
+    );
+    report
+}
+
+/// Scrubs the proof store at startup and then every `interval` in a background thread. A zero
+/// `interval` scrubs only once. Successive batches move on through the request index, so every
+/// proof is verified in turn.
+pub fn spawn_scrubber(
+    proof_db: Arc<ProofDB>,
+    interval: Duration,
+    verification: Verification,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut offset = 0;
+        loop {
+            offset += scrub(&proof_db, verification, offset).verified;
+            // NOTE(Ethan): `FileDB` does not report the records which expire, so the index and the
+            // gauges of the store are brought back in line with it here.
+            if let Err(e) = proof_db.prune_request_ids() {
+                tracing::error!("Failed to prune the request index: {:?}", e);
+            }
+            metrics::count_requests(&proof_db);
+            if interval.is_zero() {
+                break;
+            }
+            thread::sleep(interval);
+        }
+    })
+}
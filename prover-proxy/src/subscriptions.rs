@@ -0,0 +1,130 @@
+use jsonrpc_core::Result as JsonResult;
+use jsonrpc_pubsub::{typed::Sink, Session, SubscriptionId};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    interface::{Rpc, RpcImpl},
+    types::{ProofResult, RequestResult},
+};
+
+struct Watch {
+    l2_hash: String,
+    l1_head_hash: String,
+    /// The status last pushed to the subscriber, if any.
+    last_status: Option<RequestResult>,
+    sink: Sink<ProofResult>,
+}
+
+/// Subscribers of `subscribeProof`, which are pushed every status change of their request.
+#[derive(Default)]
+pub struct ProofSubscriptions {
+    next_id: AtomicU64,
+    watches: Mutex<HashMap<SubscriptionId, Watch>>,
+}
+
+impl ProofSubscriptions {
+    pub fn next_id(&self) -> SubscriptionId {
+        SubscriptionId::Number(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn add(
+        &self,
+        id: SubscriptionId,
+        l2_hash: String,
+        l1_head_hash: String,
+        sink: Sink<ProofResult>,
+    ) {
+        let watch = Watch { l2_hash, l1_head_hash, last_status: None, sink };
+        self.watches.lock().unwrap().insert(id, watch);
+    }
+
+    /// Returns `false` if there is no such subscription.
+    pub fn remove(&self, id: &SubscriptionId) -> bool {
+        self.watches.lock().unwrap().remove(id).is_some()
+    }
+
+    /// Removes the subscription once `session` is dropped, i.e. its connection is closed.
+    pub fn remove_on_close(self: &Arc<Self>, id: SubscriptionId, session: &Session) {
+        // NOTE(Ethan): a watch is notified only when its status changes, so a failed notification
+        // never tells that the subscriber of a request which does not progress is gone.
+        let subscriptions = Arc::downgrade(self);
+        session.on_drop(move || {
+            if let Some(subscriptions) = subscriptions.upgrade() {
+                subscriptions.remove(&id);
+            }
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.watches.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fetches the `ProofResult` of every watched request once, and pushes it to the subscribers
+    /// who have not seen its status yet. A subscription ends after its final result is pushed, or
+    /// when its connection is gone.
+    pub fn poll(&self, fetch: impl Fn(&str, &str) -> JsonResult<ProofResult>) {
+        let mut requests: Vec<(String, String)> = self
+            .watches
+            .lock()
+            .unwrap()
+            .values()
+            .map(|watch| (watch.l2_hash.clone(), watch.l1_head_hash.clone()))
+            .collect();
+        requests.sort();
+        requests.dedup();
+
+        // NOTE(Ethan): fetching may query the SP1 network, so it is done without holding the lock.
+        let mut results = HashMap::new();
+        for (l2_hash, l1_head_hash) in requests {
+            match fetch(&l2_hash, &l1_head_hash) {
+                Ok(proof_result) => {
+                    results.insert((l2_hash, l1_head_hash), proof_result);
+                }
+                Err(e) => tracing::error!("Failed to fetch the watched request: {:?}", e),
+            }
+        }
+
+        self.watches.lock().unwrap().retain(|id, watch| {
+            let key = (watch.l2_hash.clone(), watch.l1_head_hash.clone());
+            let proof_result = match results.get(&key) {
+                Some(proof_result) => proof_result,
+                None => return true,
+            };
+            if watch.last_status.as_ref() == Some(&proof_result.request_status) {
+                return true;
+            }
+            if watch.sink.notify(Ok(proof_result.clone())).is_err() {
+                tracing::info!("The subscriber is gone: {:?}", id);
+                return false;
+            }
+            watch.last_status = Some(proof_result.request_status.clone());
+            !proof_result.request_status.is_final()
+        });
+    }
+}
+
+/// Polls the requests with subscribers every `interval` in a background thread, so that the SP1
+/// network is queried once per request no matter how many clients wait for it.
+pub fn spawn_proof_watcher(rpc_impl: RpcImpl, interval: Duration) -> thread::JoinHandle<()> {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        if rpc_impl.subscriptions.is_empty() {
+            continue;
+        }
+        rpc_impl.subscriptions.poll(|l2_hash, l1_head_hash| {
+            rpc_impl.get_proof(l2_hash.to_string(), l1_head_hash.to_string())
+        });
+    })
+}
@@ -1,13 +1,46 @@
+use alloy_primitives::B256;
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
 use sp1_sdk::{SP1ProofWithPublicValues, SP1_CIRCUIT_VERSION as SP1_SDK_VERSION};
+use std::collections::BTreeMap;
 
-use crate::{version::PROVER_PROXY_VERSION, VERIFICATION_KEY_HASH};
+use crate::{
+    utils::{DEFAULT_FULFILLMENT_STRATEGY, PROOF_MODE},
+    version::{API_VERSION, PROVER_PROXY_VERSION},
+    MAX_DECOMPRESSED_SIZE, VERIFICATION_KEY_HASH,
+};
 
+/// The result of `spec`. The fields after `program_key` let clients negotiate features, and are
+/// defaulted for proxies older than `API_VERSION` v1.3.0.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SpecResult {
     pub version: String,
     pub sp1_version: String,
     pub program_key: String,
+    #[serde(default)]
+    pub api_version: String,
+    /// The SP1 proof modes of the proofs, e.g. `Plonk`.
+    #[serde(default)]
+    pub proof_modes: Vec<String>,
+    /// The keys of the programs which can be proven.
+    #[serde(default)]
+    pub programs: Vec<String>,
+    /// The backends which prove the requests, e.g. `network` for the SP1 network.
+    #[serde(default)]
+    pub backends: Vec<String>,
+    /// The largest witness accepted, in bytes.
+    #[serde(default)]
+    pub max_witness_size: u64,
+    /// The fulfillment strategy of requests without a close deadline.
+    #[serde(default)]
+    pub fulfillment_strategy: String,
+    /// The number of requests waiting to be submitted to the SP1 network.
+    #[serde(default)]
+    pub queue_depth: u64,
+    /// Whether the program is registered to the SP1 network.
+    #[serde(default)]
+    pub program_registered: bool,
 }
 
 impl SpecResult {
@@ -15,7 +48,15 @@ impl SpecResult {
         Self {
             version,
             sp1_version,
+            programs: vec![program_key.clone()],
             program_key,
+            api_version: API_VERSION.to_string(),
+            proof_modes: vec![format!("{:?}", PROOF_MODE)],
+            backends: vec!["network".to_string()],
+            max_witness_size: MAX_DECOMPRESSED_SIZE as u64,
+            fulfillment_strategy: format!("{:?}", DEFAULT_FULFILLMENT_STRATEGY),
+            queue_depth: 0,
+            program_registered: false,
         }
     }
 }
@@ -36,6 +77,14 @@ pub enum RequestResult {
     Processing,
     Completed,
     Failed,
+    Cancelled,
+}
+
+impl RequestResult {
+    /// Returns whether the request can no longer change without a new `requestProve`.
+    pub fn is_final(&self) -> bool {
+        matches!(self, RequestResult::Completed | RequestResult::Failed | RequestResult::Cancelled)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -95,6 +144,282 @@ impl ProofResult {
             proof: "".to_string(),
         }
     }
+
+    pub fn cancelled(request_id: String) -> Self {
+        Self {
+            request_id,
+            request_status: RequestResult::Cancelled,
+            program_key: VERIFICATION_KEY_HASH.to_string(),
+            public_values: "".to_string(),
+            proof: "".to_string(),
+        }
+    }
+}
+
+/// The result of a proof lookup by the request id of the SP1 network.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RequestProofResult {
+    pub l2_hash: String,
+    pub l1_head_hash: String,
+    #[serde(flatten)]
+    pub proof_result: ProofResult,
+}
+
+impl RequestProofResult {
+    pub fn new(l2_hash: &B256, l1_head_hash: &B256, proof_result: ProofResult) -> Self {
+        Self { l2_hash: l2_hash.to_string(), l1_head_hash: l1_head_hash.to_string(), proof_result }
+    }
+}
+
+/// The metadata of a request, stored along with it and returned by `listRequests`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RequestMetadata {
+    pub request_id: B256,
+    pub l2_hash: B256,
+    pub l1_head_hash: B256,
+    pub program_key: B256,
+    pub caller: Option<String>,
+    pub status: RequestResult,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub priority: u32,
+    /// Unix seconds by which the proof is needed.
+    pub deadline: Option<u64>,
+    /// Whether the request was submitted too late to meet its deadline.
+    pub at_risk: bool,
+    pub idempotency_key: Option<String>,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl RequestMetadata {
+    pub fn new(
+        request_id: B256,
+        l2_hash: B256,
+        l1_head_hash: B256,
+        caller: Option<String>,
+    ) -> Self {
+        let now = crate::utils::now_secs();
+        Self {
+            request_id,
+            l2_hash,
+            l1_head_hash,
+            program_key: *VERIFICATION_KEY_HASH,
+            caller,
+            status: RequestResult::Processing,
+            created_at: now,
+            updated_at: now,
+            priority: 0,
+            deadline: None,
+            at_risk: false,
+            idempotency_key: None,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+/// The result of `getStatus`: the last known state of a request, without its proof.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct StatusResult {
+    pub request_id: Option<B256>,
+    pub status: Option<RequestResult>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+    /// Unix seconds at which a request in progress is expected to complete.
+    pub estimated_completion_at: Option<u64>,
+    pub deadline: Option<u64>,
+    /// Whether the request is expected to miss its deadline.
+    pub at_risk: bool,
+}
+
+impl StatusResult {
+    pub fn new(metadata: &RequestMetadata, estimated_proving_secs: u64) -> Self {
+        let estimated_completion_at = match metadata.status {
+            RequestResult::Processing => Some(metadata.created_at + estimated_proving_secs),
+            _ => None,
+        };
+        let at_risk = match (estimated_completion_at, metadata.deadline) {
+            (Some(completion_at), Some(deadline)) => metadata.at_risk || completion_at > deadline,
+            _ => false,
+        };
+        Self {
+            request_id: Some(metadata.request_id),
+            status: Some(metadata.status.clone()),
+            created_at: Some(metadata.created_at),
+            updated_at: Some(metadata.updated_at),
+            estimated_completion_at,
+            deadline: metadata.deadline,
+            at_risk,
+        }
+    }
+
+    pub fn none() -> Self {
+        Self { status: Some(RequestResult::None), ..Default::default() }
+    }
+
+    /// The status of a request which is not submitted to the SP1 network yet, so has no request
+    /// id.
+    pub fn pending(status: RequestResult) -> Self {
+        Self { status: Some(status), ..Default::default() }
+    }
+}
+
+pub const DEFAULT_LIST_LIMIT: usize = 100;
+pub const MAX_LIST_LIMIT: usize = 1000;
+
+/// The filter of `listRequests`. Every field is optional, and the time range is applied to
+/// `created_at` as `[from, to)` in unix seconds.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ListRequestsFilter {
+    pub status: Option<RequestResult>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub program_key: Option<B256>,
+    pub caller: Option<String>,
+    pub idempotency_key: Option<String>,
+    /// Matches the requests which have every one of these labels.
+    pub labels: Option<BTreeMap<String, String>>,
+    /// The `next_cursor` of the previous page.
+    pub cursor: Option<B256>,
+    pub limit: Option<usize>,
+}
+
+impl ListRequestsFilter {
+    pub fn matches(&self, metadata: &RequestMetadata) -> bool {
+        self.status.as_ref().map_or(true, |status| *status == metadata.status) &&
+            self.from.map_or(true, |from| metadata.created_at >= from) &&
+            self.to.map_or(true, |to| metadata.created_at < to) &&
+            self.program_key.map_or(true, |key| key == metadata.program_key) &&
+            self.caller.as_ref().map_or(true, |caller| metadata.caller.as_ref() == Some(caller)) &&
+            self.idempotency_key
+                .as_ref()
+                .map_or(true, |key| metadata.idempotency_key.as_ref() == Some(key)) &&
+            self.labels.as_ref().map_or(true, |labels| {
+                labels.iter().all(|(name, value)| metadata.labels.get(name) == Some(value))
+            })
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT)
+    }
+}
+
+/// The result of `listRequests`, newest first.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ListRequestsResult {
+    pub requests: Vec<RequestMetadata>,
+    pub next_cursor: Option<B256>,
+}
+
+/// Optional parameters of `requestProve`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct RequestOptions {
+    /// The URL which is called with the `RequestProofResult` once the request is finished. Each
+    /// call with another URL adds a callback.
+    pub callback_url: Option<String>,
+    /// How the witness is encoded; `hex` if not given.
+    pub witness_encoding: Option<WitnessEncoding>,
+    /// Discards the existing request of the hashes, even a completed one, and proves them again.
+    #[serde(default)]
+    pub force: bool,
+    /// Requests of a higher priority are submitted first; 0 if not given.
+    pub priority: Option<u32>,
+    /// Unix seconds by which the proof is needed, e.g. the end of the challenge window.
+    pub deadline: Option<u64>,
+    /// A retry with the same key is answered as the first call was, and never forces again.
+    pub idempotency_key: Option<String>,
+    /// Free-form labels stored with the request, e.g. the dispute game address or the chain id.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+pub const MAX_LABELS: usize = 16;
+pub const MAX_LABEL_LEN: usize = 256;
+
+pub const MAX_BATCH_SIZE: usize = 64;
+
+/// An item of `requestProveBatch`, given either as an object or as `[l2_hash, l1_head_hash,
+/// witness(, options)]`. The witness may be `null` if a witness generator is configured.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ProveRequestItem {
+    pub l2_hash: String,
+    pub l1_head_hash: String,
+    #[serde(default)]
+    pub witness: Option<WitnessInput>,
+    #[serde(default)]
+    pub options: Option<RequestOptions>,
+}
+
+/// An item of `getProofs`, given either as an object or as `[l2_hash, l1_head_hash]`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ProofRequestItem {
+    pub l2_hash: String,
+    pub l1_head_hash: String,
+}
+
+/// The outcome of an item of a batch method, in which either `result` or `error` is set.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BatchItemResult<T> {
+    pub result: Option<T>,
+    pub error: Option<jsonrpc_core::Error>,
+}
+
+impl<T> From<jsonrpc_core::Result<T>> for BatchItemResult<T> {
+    fn from(result: jsonrpc_core::Result<T>) -> Self {
+        match result {
+            Ok(result) => Self { result: Some(result), error: None },
+            Err(error) => Self { result: None, error: Some(error) },
+        }
+    }
+}
+
+/// The progress of a chunked witness upload.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct UploadStatus {
+    pub session_id: String,
+    /// The number of witness bytes received so far, which is the offset of the next chunk.
+    pub received: u64,
+}
+
+/// The encodings of a witness, which is the bincode serialized `Vec<Vec<u8>>` of `SP1Stdin`.
+/// `zstd` and `gzip` are the base64 encoded compressed witness.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WitnessEncoding {
+    /// `0x` prefixed hex, as the witness generator returns it.
+    #[default]
+    Hex,
+    Base64,
+    Zstd,
+    Gzip,
+}
+
+impl WitnessEncoding {
+    pub fn encode(&self, buf: &[Vec<u8>]) -> Result<String> {
+        let serialized = bincode::serialize(buf)?;
+        let encoded = match self {
+            WitnessEncoding::Hex => "0x".to_string() + hex::encode(&serialized).as_ref(),
+            WitnessEncoding::Base64 => BASE64.encode(&serialized),
+            WitnessEncoding::Zstd => BASE64.encode(zstd::encode_all(serialized.as_slice(), 0)?),
+            WitnessEncoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                std::io::Write::write_all(&mut encoder, &serialized)?;
+                BASE64.encode(encoder.finish()?)
+            }
+        };
+        Ok(encoded)
+    }
+
+    pub fn decode(&self, witness: &str) -> Result<Vec<Vec<u8>>> {
+        let serialized = match self {
+            WitnessEncoding::Hex => hex::decode(witness.trim_start_matches("0x"))?,
+            WitnessEncoding::Base64 => BASE64.decode(witness)?,
+            WitnessEncoding::Zstd => crate::utils::unzstd(&BASE64.decode(witness)?)?,
+            WitnessEncoding::Gzip => crate::utils::gunzip(&BASE64.decode(witness)?)?,
+        };
+        bincode::deserialize(&serialized)
+            .map_err(|e| anyhow!("Failed to deserialize the witness: {}", e))
+    }
 }
 
 /// The result of a witness method.
@@ -141,4 +466,62 @@ impl WitnessResult {
     pub fn get_witness_buf(&self) -> Vec<Vec<u8>> {
         Self::string_to_witness_buf(&self.witness)
     }
+
+    pub fn new_from_witness_buf_with_encoding(
+        status: RequestResult,
+        buf: Vec<Vec<u8>>,
+        encoding: WitnessEncoding,
+    ) -> Result<Self> {
+        Ok(Self::new(status, encoding.encode(&buf)?))
+    }
+
+    /// Unlike `string_to_witness_buf`, it fails instead of panicking on a malformed witness.
+    pub fn decode_witness_buf(witness: &str, encoding: WitnessEncoding) -> Result<Vec<Vec<u8>>> {
+        encoding.decode(witness)
+    }
+
+    pub fn get_witness_buf_with_encoding(&self, encoding: WitnessEncoding) -> Result<Vec<Vec<u8>>> {
+        Self::decode_witness_buf(&self.witness, encoding)
+    }
+
+    /// Checks that it is a completed witness of the program which the proxy proves.
+    pub fn check(&self) -> Result<()> {
+        if self.status != RequestResult::Completed {
+            bail!("The witness is not completed: {:?}", self.status);
+        }
+        let program_key = self
+            .program_key
+            .parse::<B256>()
+            .map_err(|e| anyhow!("Invalid program key {:?}: {}", self.program_key, e))?;
+        if program_key != *VERIFICATION_KEY_HASH {
+            bail!(
+                "The witness is for the program {}, but the proxy proves {}",
+                program_key,
+                *VERIFICATION_KEY_HASH
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The witness of `requestProve`, given either as the witness string or as the whole
+/// `WitnessResult` from the witness generator.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum WitnessInput {
+    Result(WitnessResult),
+    Witness(String),
+}
+
+impl WitnessInput {
+    /// Returns the witness string, once a `WitnessResult` passes `WitnessResult::check`.
+    pub fn into_witness(self) -> Result<String> {
+        match self {
+            Self::Result(witness_result) => {
+                witness_result.check()?;
+                Ok(witness_result.witness)
+            }
+            Self::Witness(witness) => Ok(witness),
+        }
+    }
 }
@@ -0,0 +1,232 @@
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use alloy_primitives::{keccak256, B256};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    crypto::KeyRing,
+    proof_db::Sealed,
+    types::{RequestOptions, UploadStatus},
+    utils::{now_secs, KeyedLock},
+    MAX_DECOMPRESSED_SIZE,
+};
+
+/// The largest uploaded witness, which is the one `spec` reports for any witness.
+pub const MAX_UPLOAD_SIZE: u64 = MAX_DECOMPRESSED_SIZE as u64;
+static UPLOAD_EXPIRING_SECS: u64 = 24 * 60 * 60;
+
+/// An upload in progress, kept next to its data as `<session_id>.session`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub session_id: String,
+    pub l2_hash: String,
+    pub l1_head_hash: String,
+    pub options: Option<RequestOptions>,
+    /// The number of witness bytes received so far.
+    pub received: u64,
+    /// The length of the data file which holds `received` bytes. Anything beyond it is a partly
+    /// written chunk, which is discarded on the next append.
+    pub data_len: u64,
+    pub created_at: u64,
+}
+
+impl UploadSession {
+    pub fn status(&self) -> UploadStatus {
+        UploadStatus { session_id: self.session_id.clone(), received: self.received }
+    }
+}
+
+/// Witnesses which are uploaded in chunks, assembled on disk before they are submitted.
+///
+/// Each chunk is appended to `<session_id>.data` as a length-prefixed `Sealed` frame, encrypted
+/// with the key ring if one is given.
+pub struct UploadStore {
+    path: PathBuf,
+    key_ring: Option<KeyRing>,
+    lock: KeyedLock,
+}
+
+impl UploadStore {
+    pub fn new<P: AsRef<Path>>(path: P, key_ring: Option<KeyRing>) -> Self {
+        Self { path: path.as_ref().to_path_buf(), key_ring, lock: KeyedLock::default() }
+    }
+
+    fn session_path(&self, session_id: &str) -> PathBuf {
+        self.path.join(format!("{}.session", session_id))
+    }
+
+    fn data_path(&self, session_id: &str) -> PathBuf {
+        self.path.join(format!("{}.data", session_id))
+    }
+
+    fn save(&self, session: &UploadSession) -> Result<()> {
+        let data = bincode::serialize(session)
+            .map_err(|e| anyhow!("Failed to serialize the upload session: {}", e))?;
+        let path = self.session_path(&session.session_id);
+        let tmp_path = path.with_extension("session.tmp");
+        fs::write(&tmp_path, data)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Returns `None` if there is no such session.
+    pub fn get(&self, session_id: &str) -> Result<Option<UploadSession>> {
+        // NOTE(Ethan): the session id names files, so it must not be able to escape the directory.
+        if session_id.is_empty() || !session_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("Invalid session id: {:?}", session_id);
+        }
+        match fs::read(self.session_path(session_id)) {
+            Ok(data) => bincode::deserialize(&data)
+                .map(Some)
+                .map_err(|e| anyhow!("Failed to deserialize the upload session: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn begin(
+        &self,
+        l2_hash: String,
+        l1_head_hash: String,
+        options: Option<RequestOptions>,
+    ) -> Result<UploadSession> {
+        fs::create_dir_all(&self.path)?;
+        self.remove_expired();
+
+        let mut session_id = [0u8; 16];
+        OsRng.fill_bytes(&mut session_id);
+        let session = UploadSession {
+            session_id: hex::encode(session_id),
+            l2_hash,
+            l1_head_hash,
+            options,
+            received: 0,
+            data_len: 0,
+            created_at: now_secs(),
+        };
+        File::create(self.data_path(&session.session_id))?;
+        self.save(&session)?;
+        Ok(session)
+    }
+
+    /// Appends `chunk` at `offset`. A chunk which was already received, e.g. when a client
+    /// retries after a lost response, is ignored; any other `offset` than `received` fails.
+    pub fn append(&self, session_id: &str, offset: u64, chunk: &[u8]) -> Result<UploadSession> {
+        let _guard = self.lock.lock(session_id);
+        let mut session =
+            self.get(session_id)?.ok_or_else(|| anyhow!("Unknown session: {}", session_id))?;
+        let end = match offset.checked_add(chunk.len() as u64) {
+            Some(end) => end,
+            None => bail!("The chunk at {} exceeds {} bytes", offset, MAX_UPLOAD_SIZE),
+        };
+        if end <= session.received {
+            return Ok(session);
+        }
+        if offset != session.received {
+            bail!("The next chunk must start at {}, but it starts at {}", session.received, offset);
+        }
+        if end > MAX_UPLOAD_SIZE {
+            bail!("The upload exceeds {} bytes", MAX_UPLOAD_SIZE);
+        }
+
+        let aad = Self::frame_aad(session_id, offset);
+        let sealed = match &self.key_ring {
+            Some(key_ring) => Sealed::from_ciphertext(key_ring.encrypt(chunk, &aad)?),
+            None => Sealed::new(chunk.to_vec()),
+        };
+        let frame = bincode::serialize(&sealed)
+            .map_err(|e| anyhow!("Failed to serialize the chunk: {}", e))?;
+
+        let mut file = OpenOptions::new().write(true).open(self.data_path(session_id))?;
+        file.set_len(session.data_len)?;
+        file.seek(SeekFrom::End(0))?;
+        file.write_all(&(frame.len() as u64).to_le_bytes())?;
+        file.write_all(&frame)?;
+        file.sync_data()?;
+
+        session.received += chunk.len() as u64;
+        session.data_len += 8 + frame.len() as u64;
+        self.save(&session)?;
+        Ok(session)
+    }
+
+    /// Reads the uploaded witness back and checks it against `hash`, the keccak256 of the whole
+    /// witness.
+    pub fn assemble(&self, session_id: &str, hash: &B256) -> Result<(UploadSession, Vec<u8>)> {
+        let _guard = self.lock.lock(session_id);
+        let session =
+            self.get(session_id)?.ok_or_else(|| anyhow!("Unknown session: {}", session_id))?;
+
+        // NOTE(Ethan): the lengths read from disk are checked before anything is allocated by
+        // them, so that a corrupted session or frame cannot exhaust the memory.
+        if session.received > MAX_UPLOAD_SIZE {
+            bail!("The upload of {} bytes exceeds {} bytes", session.received, MAX_UPLOAD_SIZE);
+        }
+        let file = File::open(self.data_path(session_id))?;
+        let mut remaining = session.data_len.min(file.metadata()?.len());
+        let mut reader = BufReader::new(file.take(remaining));
+        let mut witness = Vec::with_capacity(session.received as usize);
+        let mut len = [0u8; 8];
+        while reader.read_exact(&mut len).is_ok() {
+            remaining -= len.len() as u64;
+            let frame_len = u64::from_le_bytes(len);
+            if frame_len > remaining {
+                bail!("A chunk of {} bytes exceeds the {} bytes left", frame_len, remaining);
+            }
+            remaining -= frame_len;
+            let mut frame = vec![0u8; frame_len as usize];
+            reader.read_exact(&mut frame)?;
+            let sealed: Sealed = bincode::deserialize(&frame)
+                .map_err(|e| anyhow!("Failed to deserialize a chunk: {}", e))?;
+            let aad = Self::frame_aad(session_id, witness.len() as u64);
+            witness.extend(sealed.open(self.key_ring.as_ref(), &aad)?);
+        }
+        if witness.len() as u64 != session.received {
+            bail!("Expected {} bytes, but read {} bytes", session.received, witness.len());
+        }
+
+        let computed = keccak256(&witness);
+        if computed != *hash {
+            bail!("Hash mismatch: expected {}, computed {}", hash, computed);
+        }
+        Ok((session, witness))
+    }
+
+    pub fn remove(&self, session_id: &str) {
+        let _ = fs::remove_file(self.data_path(session_id));
+        let _ = fs::remove_file(self.session_path(session_id));
+    }
+
+    fn remove_expired(&self) {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let now = now_secs();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("session") {
+                continue;
+            }
+            let session_id = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(session_id) => session_id.to_string(),
+                None => continue,
+            };
+            if let Ok(Some(session)) = self.get(&session_id) {
+                if session.created_at + UPLOAD_EXPIRING_SECS < now {
+                    tracing::info!("Removed an expired upload session: {}", session_id);
+                    self.remove(&session_id);
+                }
+            }
+        }
+    }
+
+    fn frame_aad(session_id: &str, offset: u64) -> Vec<u8> {
+        [session_id.as_bytes(), &offset.to_le_bytes()].concat()
+    }
+}
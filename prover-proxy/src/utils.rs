@@ -1,15 +1,26 @@
 use alloy_primitives::B256;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use sp1_sdk::{
     network::{
-        NetworkClient,
-        proto::network::{ProofMode, FulfillmentStatus},
+        proto::network::{FulfillmentStatus, ProofMode, ProofRequest},
+        FulfillmentStrategy, NetworkClient,
     },
     SP1_CIRCUIT_VERSION as SP1_SDK_VERSION, {SP1ProofWithPublicValues, SP1Stdin},
 };
-use std::{fs::File, sync::Arc};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::Read,
+    sync::{Arc, Condvar, Mutex},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
-use crate::{proof_db::ProofDB, types::{RequestResult, WitnessResult}, MAX_CYCLES, VERIFICATION_KEY_HASH};
+use crate::{
+    metrics,
+    proof_db::ProofDB,
+    types::{RequestResult, WitnessResult},
+    MAX_CYCLES, MAX_DECOMPRESSED_SIZE, VERIFICATION_KEY_HASH,
+};
 
 pub fn block_on<T>(fut: impl std::future::Future<Output = T>) -> T {
     use tokio::task::block_in_place;
@@ -24,27 +35,141 @@ pub fn block_on<T>(fut: impl std::future::Future<Output = T>) -> T {
     }
 }
 
-pub fn request_prove_to_sp1(client: &Arc<NetworkClient>, witness: String) -> Result<B256> {
-    // Recover a SP1Stdin from the witness string.
+/// Serializes tasks on the same request while tasks on different requests run concurrently.
+#[derive(Default)]
+pub struct KeyedLock {
+    busy: Mutex<HashSet<String>>,
+    released: Condvar,
+}
+
+pub struct KeyedLockGuard<'a> {
+    lock: &'a KeyedLock,
+    key: String,
+}
+
+impl KeyedLock {
+    pub fn lock(&self, key: &str) -> KeyedLockGuard<'_> {
+        let mut busy = self.busy.lock().unwrap();
+        while busy.contains(key) {
+            busy = self.released.wait(busy).unwrap();
+        }
+        busy.insert(key.to_string());
+        KeyedLockGuard { lock: self, key: key.to_string() }
+    }
+}
+
+impl Drop for KeyedLockGuard<'_> {
+    fn drop(&mut self) {
+        self.lock.busy.lock().unwrap().remove(&self.key);
+        self.lock.released.notify_all();
+    }
+}
+
+/// Applies `f` to every item on its own scoped thread and returns the outputs in order. The threads
+/// enter the caller's tokio runtime, if any, so that `block_on` reuses it.
+pub fn run_concurrently<I: Send, T: Send>(items: Vec<I>, f: impl Fn(I) -> T + Sync) -> Vec<T> {
+    let handle = tokio::runtime::Handle::try_current().ok();
+    std::thread::scope(|scope| {
+        let tasks: Vec<_> = items
+            .into_iter()
+            .map(|item| {
+                let (f, handle) = (&f, &handle);
+                scope.spawn(move || {
+                    let _enter = handle.as_ref().map(|handle| handle.enter());
+                    f(item)
+                })
+            })
+            .collect();
+        tasks.into_iter().map(|task| task.join().unwrap()).collect()
+    })
+}
+
+/// Reads `reader` to the end, failing if it yields more than `MAX_DECOMPRESSED_SIZE` bytes.
+fn read_to_end_limited(reader: impl Read) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(MAX_DECOMPRESSED_SIZE as u64 + 1).read_to_end(&mut data)?;
+    if data.len() > MAX_DECOMPRESSED_SIZE {
+        bail!("The data exceeds {} bytes after decompression", MAX_DECOMPRESSED_SIZE);
+    }
+    Ok(data)
+}
+
+pub fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    read_to_end_limited(flate2::read::GzDecoder::new(data))
+        .map_err(|e| anyhow!("Failed to decompress gzip: {}", e))
+}
+
+pub fn unzstd(data: &[u8]) -> Result<Vec<u8>> {
+    zstd::stream::read::Decoder::new(data)
+        .map_err(anyhow::Error::from)
+        .and_then(read_to_end_limited)
+        .map_err(|e| anyhow!("Failed to decompress zstd: {}", e))
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("Time went backwards").as_secs()
+}
+
+pub fn now_nanos() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("Time went backwards").as_nanos() as u64
+}
+
+/// The mode of every proof requested to the SP1 network.
+pub const PROOF_MODE: ProofMode = ProofMode::Plonk;
+/// The fulfillment strategy of requests without a close deadline.
+pub const DEFAULT_FULFILLMENT_STRATEGY: FulfillmentStrategy = FulfillmentStrategy::Hosted;
+
+static DEFAULT_PROVING_TIMEOUT_SECS: u64 = 2 * 60 * 60;
+static MIN_PROVING_TIMEOUT_SECS: u64 = 10 * 60;
+
+/// Chooses the fulfillment strategy and the timeout of a request which is due by `deadline`.
+/// The timeout never runs past the deadline unless it would be shorter than 10 minutes, and a
+/// request with less than twice `estimated_proving_secs` left goes to the reserved capacity.
+pub fn fulfillment_plan(
+    deadline: Option<u64>,
+    estimated_proving_secs: u64,
+) -> (FulfillmentStrategy, u64) {
+    let remaining = match deadline {
+        Some(deadline) => deadline.saturating_sub(now_secs()),
+        None => return (DEFAULT_FULFILLMENT_STRATEGY, DEFAULT_PROVING_TIMEOUT_SECS),
+    };
+    let timeout_secs = remaining.clamp(MIN_PROVING_TIMEOUT_SECS, DEFAULT_PROVING_TIMEOUT_SECS);
+    let strategy = if remaining < 2 * estimated_proving_secs {
+        FulfillmentStrategy::Reserved
+    } else {
+        DEFAULT_FULFILLMENT_STRATEGY
+    };
+    (strategy, timeout_secs)
+}
+
+pub fn request_prove_to_sp1(
+    client: &Arc<NetworkClient>,
+    witness_buf: Vec<Vec<u8>>,
+    strategy: FulfillmentStrategy,
+    timeout_secs: u64,
+) -> Result<B256> {
     let mut sp1_stdin = SP1Stdin::new();
-    sp1_stdin.buffer = WitnessResult::string_to_witness_buf(&witness);
+    sp1_stdin.buffer = witness_buf;
 
     // Send a request to generate a proof to the sp1 network.
     tracing::debug!("ready to send request to SP1 network prover");
-    
+
+    let started = Instant::now();
     let response = block_on(async move {
         client
             .request_proof(
                 *VERIFICATION_KEY_HASH,
                 &sp1_stdin,
-                ProofMode::Plonk,
+                PROOF_MODE,
                 SP1_SDK_VERSION,
-                sp1_sdk::network::FulfillmentStrategy::Hosted,
-                7200,
+                strategy,
+                timeout_secs,
                 MAX_CYCLES,
             )
             .await
-    })?;
+    });
+    metrics::observe_sp1("request_proof", &metrics::SP1_SUBMISSION_DURATION, started, &response);
+    let response = response?;
 
     let request_id = B256::from_slice(&response.body.unwrap().request_id);
     tracing::debug!("Sent the request to SP1 network prover: {:?}", request_id);
@@ -82,19 +207,79 @@ pub fn status_from_i32(value: i32) -> Option<FulfillmentStatus> {
     }
 }
 
+static FULFILLED_REQUESTS_PAGE_SIZE: u32 = 100;
+static FULFILLED_REQUESTS_MAX_PAGES: u32 = 10;
+/// How much earlier than the proxy the SP1 network may have created a request.
+static CLOCK_SKEW_SECS: u64 = 5 * 60;
+
+/// Looks up `request_id` among the requests of the program which the SP1 network fulfilled, of
+/// those it created since `since`.
+pub fn find_fulfilled_request(
+    client: &NetworkClient,
+    request_id: &B256,
+    since: u64,
+) -> Result<Option<ProofRequest>> {
+    for page in 1..=FULFILLED_REQUESTS_MAX_PAGES {
+        let response = block_on(client.get_filtered_proof_requests(
+            None,
+            Some(FulfillmentStatus::Fulfilled as i32),
+            None,
+            None,
+            Some(VERIFICATION_KEY_HASH.to_vec()),
+            None,
+            None,
+            Some(since.saturating_sub(CLOCK_SKEW_SECS)),
+            None,
+            Some(FULFILLED_REQUESTS_PAGE_SIZE),
+            Some(page),
+            None,
+        ))?;
+        let len = response.requests.len();
+        let found = response
+            .requests
+            .into_iter()
+            .find(|request| request.request_id.as_slice() == request_id.as_slice());
+        if found.is_some() || len < FULFILLED_REQUESTS_PAGE_SIZE as usize {
+            return Ok(found);
+        }
+    }
+    Ok(None)
+}
+
+/// Records the time to proof of a request which was just found completed, as the SP1 network
+/// reports it rather than when it was polled.
+fn observe_time_to_proof(client: &NetworkClient, request_id: &B256, created_at: u64) {
+    match find_fulfilled_request(client, request_id, created_at) {
+        Ok(Some(request)) => metrics::observe_time_to_proof(&request),
+        Ok(None) => tracing::warn!("The fulfillment of {:?} was not found", request_id),
+        Err(e) => tracing::warn!("Failed to fetch the fulfillment of {:?}: {:?}", request_id, e),
+    }
+}
+
 pub fn get_status_by_remote_id(
     client: &Arc<NetworkClient>,
     proof_db: &Arc<ProofDB>,
     request_id: B256,
 ) -> RequestResult {
-    let (status, maybe_proof) =
-        match block_on(async { client.get_proof_request_status(request_id, None).await }) {
-            Ok(res) => res,
-            Err(_) => return RequestResult::None,
-        };
+    // A cancelled request is never polled again.
+    let metadata = proof_db.get_request_metadata(&request_id);
+    if let Some(metadata) = &metadata {
+        if metadata.status == RequestResult::Cancelled {
+            tracing::info!("The request was cancelled: {:?}", request_id);
+            return RequestResult::Cancelled;
+        }
+    }
+
+    let started = Instant::now();
+    let response = block_on(async { client.get_proof_request_status(request_id, None).await });
+    metrics::observe_sp1("status_query", &metrics::SP1_STATUS_QUERY_DURATION, started, &response);
+    let (status, maybe_proof) = match response {
+        Ok(res) => res,
+        Err(_) => return RequestResult::None,
+    };
 
     tracing::info!("Fetched proof request status: {:?}", status);
-    match status_from_i32(status.fulfillment_status).unwrap() {
+    let result = match status_from_i32(status.fulfillment_status).unwrap() {
         FulfillmentStatus::Fulfilled => {
             proof_db.set_proof(&request_id, &maybe_proof.unwrap()).unwrap();
             RequestResult::Completed
@@ -105,7 +290,19 @@ pub fn get_status_by_remote_id(
             tracing::error!("The proof status is unspecified: {:?}", request_id);
             RequestResult::None
         }
+    };
+
+    if result != RequestResult::None {
+        if let Err(e) = proof_db.update_request_status(&request_id, result.clone()) {
+            tracing::error!("Failed to update the status of {:?}: {:?}", request_id, e);
+        }
     }
+    if let Some(metadata) = metadata {
+        if result == RequestResult::Completed && metadata.status != RequestResult::Completed {
+            observe_time_to_proof(client, &request_id, metadata.created_at);
+        }
+    }
+    result
 }
 
 pub fn get_proof_by_local_id(
@@ -126,4 +323,4 @@ pub fn load_witness(witness_data: &String) -> Result<WitnessResult> {
     let witness_result = serde_json::from_reader(reader)?;
 
     Ok(witness_result)
-}
\ No newline at end of file
+}
@@ -1 +1,4 @@
 pub const PROVER_PROXY_VERSION: &str = "v1.2.0";
+/// The version of the interface, which is that of the OpenRPC document. Bumped whenever a method
+/// or a type of it changes.
+pub const API_VERSION: &str = "v1.3.0";
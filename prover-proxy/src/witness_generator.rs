@@ -0,0 +1,73 @@
+use alloy_primitives::B256;
+use anyhow::{anyhow, bail, Result};
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee_core::{client::ClientT, rpc_params};
+use std::time::{Duration, Instant};
+
+use crate::{
+    types::{RequestResult, WitnessResult},
+    MAX_DECOMPRESSED_SIZE,
+};
+
+static WITNESS_POLLING_SECS: u64 = 10;
+static WITNESS_TIMEOUT_SECS: u64 = 2 * 60 * 60;
+
+/// A client of the witness generator, which fetches witnesses for `requestProve`s that are given
+/// only the hashes.
+pub struct WitnessGenerator {
+    runtime: tokio::runtime::Runtime,
+    client: HttpClient,
+}
+
+impl WitnessGenerator {
+    pub fn new(url: &str) -> Result<Self> {
+        // NOTE(Ethan): fetches run on threads without a runtime, so the client keeps its own to
+        // reuse connections across them.
+        let runtime =
+            tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build()?;
+        let client = {
+            let _enter = runtime.enter();
+            HttpClientBuilder::default()
+                .max_request_body_size(MAX_DECOMPRESSED_SIZE as u32)
+                .request_timeout(Duration::from_secs(60))
+                .build(url)
+                .map_err(|e| anyhow!("Failed to connect to the witness generator: {}", e))?
+        };
+        Ok(Self { runtime, client })
+    }
+
+    /// Requests the witness of the hashes, and polls the witness generator until it is generated.
+    pub fn fetch(&self, l2_hash: &B256, l1_head_hash: &B256) -> Result<WitnessResult> {
+        self.runtime.block_on(async {
+            let status: RequestResult = self
+                .client
+                .request("requestWitness", rpc_params![l2_hash, l1_head_hash])
+                .await
+                .map_err(|e| anyhow!("Failed to request the witness: {}", e))?;
+            tracing::info!(
+                "Requested the witness: {:?}, {:?}: {:?}",
+                l2_hash,
+                l1_head_hash,
+                status
+            );
+
+            let started = Instant::now();
+            loop {
+                let witness_result: WitnessResult = self
+                    .client
+                    .request("getWitness", rpc_params![l2_hash, l1_head_hash])
+                    .await
+                    .map_err(|e| anyhow!("Failed to get the witness: {}", e))?;
+                match witness_result.status {
+                    RequestResult::Completed => return Ok(witness_result),
+                    RequestResult::Processing => {}
+                    status => bail!("The witness generator returned {:?}", status),
+                }
+                if started.elapsed() > Duration::from_secs(WITNESS_TIMEOUT_SECS) {
+                    bail!("The witness is not generated in {} seconds", WITNESS_TIMEOUT_SECS);
+                }
+                tokio::time::sleep(Duration::from_secs(WITNESS_POLLING_SECS)).await;
+            }
+        })
+    }
+}
@@ -0,0 +1,100 @@
+mod utils;
+
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{
+    archive::ProofArchive,
+    proof_db::{
+        ArchivedMetadata, ArchivedProof, Envelope, ProofDB, Sealed, StoredProof,
+        ARCHIVED_METADATA_VERSION, ARCHIVE_VERSION,
+    },
+    types::RequestMetadata,
+};
+use tempfile::TempDir;
+
+#[test]
+fn test_filesystem_archive() -> Result<()> {
+    let directory = TempDir::new()?;
+    let archive = ProofArchive::from_url(&format!("file://{}", directory.path().display()))?;
+
+    assert_eq!(archive.get("proofs/missing")?, None);
+
+    archive.put("proofs/0x01", vec![1, 2, 3])?;
+    assert_eq!(archive.get("proofs/0x01")?, Some(vec![1, 2, 3]));
+
+    // Another instance sharing the archive reads the same object.
+    let shared = ProofArchive::from_url(&format!("file://{}", directory.path().display()))?;
+    assert_eq!(shared.get("proofs/0x01")?, Some(vec![1, 2, 3]));
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_archived_proof() -> Result<()> {
+    let directory = TempDir::new()?;
+    let url = format!("file://{}", directory.path().join("archive").display());
+    let open = |name: &str| -> Result<ProofDB> {
+        let path = directory.path().join(name);
+        Ok(ProofDB::new(path.to_str().unwrap()).with_archive(Some(ProofArchive::from_url(&url)?)))
+    };
+    let (request_id, l2_hash, l1_head_hash) =
+        (B256::repeat_byte(1), B256::repeat_byte(2), B256::repeat_byte(3));
+    let writer = open("writer")?;
+    let metadata = RequestMetadata::new(request_id, l2_hash, l1_head_hash, None);
+    writer.set_request_metadata(&metadata)?;
+    writer.set_proof(&request_id, &utils::dummy_proof(b"public values"))?;
+
+    // Another proxy restores the proof along with its metadata.
+    let reader = open("reader")?;
+    assert!(reader.get_proof_by_id(&request_id).is_some());
+    assert_eq!(reader.get_request_metadata(&request_id), Some(metadata));
+
+    // Metadata of an unknown layout is skipped, and the proof is still restored.
+    let archive = ProofArchive::from_url(&url)?;
+    let put = |request_id: &B256, version: u32, metadata_version: u32| -> Result<()> {
+        let archived = ArchivedProof {
+            request_id: *request_id,
+            metadata: Some(ArchivedMetadata { version: metadata_version, payload: vec![0xff] }),
+            proof: StoredProof::new(&utils::dummy_proof(b"public values"))?,
+        };
+        let body = bincode::serialize(&Sealed::new(bincode::serialize(&archived)?))?;
+        let data = bincode::serialize(&Envelope { version, body })?;
+        archive.put(&format!("proofs/{}", request_id), data)
+    };
+    let request_id = B256::repeat_byte(4);
+    put(&request_id, ARCHIVE_VERSION, ARCHIVED_METADATA_VERSION + 1)?;
+    assert!(reader.get_proof_by_id(&request_id).is_some());
+    assert_eq!(reader.get_request_metadata(&request_id), None);
+
+    // An object of a newer layout is not decoded.
+    let request_id = B256::repeat_byte(5);
+    put(&request_id, ARCHIVE_VERSION + 1, ARCHIVED_METADATA_VERSION)?;
+    assert!(reader.get_proof_by_id(&request_id).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_request_id_read_through() -> Result<()> {
+    let directory = TempDir::new()?;
+    let url = format!("file://{}", directory.path().join("archive").display());
+    let open = |name: &str| -> Result<ProofDB> {
+        let path = directory.path().join(name);
+        Ok(ProofDB::new(path.to_str().unwrap()).with_archive(Some(ProofArchive::from_url(&url)?)))
+    };
+    let (request_id, l2_hash, l1_head_hash) =
+        (B256::repeat_byte(1), B256::repeat_byte(2), B256::repeat_byte(3));
+    let writer = open("writer")?;
+    writer.set_request_metadata(&RequestMetadata::new(request_id, l2_hash, l1_head_hash, None))?;
+    writer.set_request_id(&l2_hash, &l1_head_hash, &request_id)?;
+    writer.set_proof(&request_id, &utils::dummy_proof(b"public values"))?;
+
+    // Only fetching the proof reads the archive, after which the mapping is local.
+    let reader = open("reader")?;
+    assert_eq!(reader.get_request_id(&l2_hash, &l1_head_hash), None);
+    assert!(reader.get_proof(&l2_hash, &l1_head_hash).is_some());
+    assert_eq!(reader.get_request_id(&l2_hash, &l1_head_hash), Some(request_id));
+    assert_eq!(reader.find_request_id(&B256::ZERO, &l1_head_hash), None);
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+mod utils;
+
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{cache::ProofCache, proof_db::ProofDB, types::ProofResult};
+use tempfile::TempDir;
+
+#[test]
+fn test_proof_cache() {
+    let cache = ProofCache::new(2);
+    let (first, second, third) = (B256::repeat_byte(1), B256::repeat_byte(2), B256::repeat_byte(3));
+    let result = |request_id: &B256| ProofResult::processing(request_id.to_string());
+
+    assert_eq!(cache.get(&first), None);
+    cache.put(first, result(&first));
+    cache.put(second, result(&second));
+    assert_eq!(cache.get(&first), Some(result(&first)));
+
+    // The least recently used result is evicted.
+    cache.put(third, result(&third));
+    assert_eq!(cache.get(&second), None);
+    assert_eq!(cache.get(&first), Some(result(&first)));
+
+    cache.invalidate(&first);
+    assert_eq!(cache.get(&first), None);
+
+    // A zero capacity disables the cache.
+    let disabled = ProofCache::new(0);
+    disabled.put(first, result(&first));
+    assert_eq!(disabled.get(&first), None);
+}
+
+#[test]
+fn test_proof_cache_and_quarantine() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let request_id = B256::repeat_byte(1);
+    assert_eq!(proof_db.get_proof_result(&request_id), None);
+
+    proof_db.set_proof(&request_id, &utils::dummy_proof(b"first"))?;
+    let first = proof_db.get_proof_result(&request_id).expect("the proof is stored");
+    assert_eq!(proof_db.get_proof_result(&request_id), Some(first.clone()));
+
+    // A quarantined proof is not served from the cache.
+    proof_db.quarantine(&request_id, "corrupted".to_string())?;
+    assert_eq!(proof_db.get_proof_result(&request_id), None);
+
+    // A fresh proof releases it and replaces the cached result.
+    proof_db.set_proof(&request_id, &utils::dummy_proof(b"second"))?;
+    let second = proof_db.get_proof_result(&request_id).expect("the proof is stored");
+    assert_ne!(second.public_values, first.public_values);
+    assert_eq!(proof_db.get_proof_result(&request_id), Some(second));
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{
+    callbacks::{sign, CallbackDispatcher, SIGNATURE_HEADER},
+    proof_db::{PendingCallback, ProofDB},
+    types::{ProofResult, RequestProofResult, RequestResult},
+    utils::now_secs,
+};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+};
+use tempfile::TempDir;
+
+/// The signature and the body of a callback.
+type Received = (Option<String>, Vec<u8>);
+
+/// A stand-in for the receiver of callbacks. It answers the requests with `statuses` in order,
+/// and forwards the signature and the body of each request.
+fn serve(statuses: Vec<u16>) -> (String, mpsc::Receiver<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/callback", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for (stream, status) in listener.incoming().zip(statuses) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let (mut signature, mut length) = (None, 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    match name.to_ascii_lowercase().as_str() {
+                        SIGNATURE_HEADER => signature = Some(value.trim().to_string()),
+                        "content-length" => length = value.trim().parse().unwrap(),
+                        _ => {}
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            sender.send((signature, body)).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+        }
+    });
+    (url, receiver)
+}
+
+#[test]
+fn test_callback_outbox() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let (url, received) = serve(vec![500, 200]);
+
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(2), B256::repeat_byte(3));
+    let request_id = B256::repeat_byte(1);
+    proof_db.add_callback(PendingCallback::new(&l2_hash, &l1_head_hash, Some(request_id), url))?;
+    let result = RequestProofResult::new(
+        &l2_hash,
+        &l1_head_hash,
+        ProofResult::failed(request_id.to_string()),
+    );
+    let dispatcher = CallbackDispatcher::new(b"secret")?;
+
+    // A failed delivery stays in the outbox, and is retried after a backoff.
+    assert_eq!(dispatcher.dispatch(&proof_db, |_| Ok(result.clone())), 0);
+    received.recv()?;
+    let mut callback = proof_db.pending_callbacks().pop().unwrap();
+    assert_eq!(callback.attempts, 1);
+    assert!(callback.next_attempt_at > now_secs());
+    assert_eq!(dispatcher.dispatch(&proof_db, |_| Ok(result.clone())), 0);
+
+    callback.next_attempt_at = 0;
+    proof_db.update_callback(&callback)?;
+    assert_eq!(dispatcher.dispatch(&proof_db, |_| Ok(result.clone())), 1);
+    let (signature, body) = received.recv()?;
+    assert_eq!(signature, Some(format!("sha256={}", sign(b"secret", &body))));
+    assert_eq!(serde_json::from_slice::<RequestProofResult>(&body)?, result);
+    assert!(proof_db.pending_callbacks().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_callbacks_of_accepted_requests() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let (first_url, first_received) = serve(vec![200]);
+    let (second_url, second_received) = serve(vec![200]);
+    let dispatcher = CallbackDispatcher::new(b"secret")?;
+    let unreachable = |_: &B256| -> jsonrpc_core::Result<RequestProofResult> {
+        panic!("a request which is not submitted is never fetched")
+    };
+
+    // Every url of the hashes gets a callback, and nothing is delivered before the request is
+    // submitted.
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(2), B256::repeat_byte(3));
+    for url in [&first_url, &second_url, &first_url] {
+        proof_db.add_callback(PendingCallback::new(&l2_hash, &l1_head_hash, None, url.clone()))?;
+    }
+    assert_eq!(proof_db.pending_callbacks().len(), 2);
+    assert_eq!(dispatcher.dispatch(&proof_db, unreachable), 0);
+
+    // The request of a successful submission is attached to them.
+    let request_id = B256::repeat_byte(1);
+    proof_db.attach_callbacks(&l2_hash, &l1_head_hash, &request_id)?;
+    let result = RequestProofResult::new(
+        &l2_hash,
+        &l1_head_hash,
+        ProofResult::cancelled(request_id.to_string()),
+    );
+    let fetched = |id: &B256| {
+        assert_eq!(*id, request_id);
+        Ok(result.clone())
+    };
+    assert_eq!(dispatcher.dispatch(&proof_db, fetched), 2);
+    for received in [&first_received, &second_received] {
+        let (_, body) = received.recv()?;
+        assert_eq!(serde_json::from_slice::<RequestProofResult>(&body)?, result);
+    }
+    assert!(proof_db.pending_callbacks().is_empty());
+
+    // A request which fails before it reaches the SP1 network is reported as failed.
+    let (url, received) = serve(vec![200]);
+    proof_db.add_callback(PendingCallback::new(&l2_hash, &l1_head_hash, None, url))?;
+    proof_db.fail_callbacks(&l2_hash, &l1_head_hash)?;
+    assert_eq!(dispatcher.dispatch(&proof_db, unreachable), 1);
+    let (_, body) = received.recv()?;
+    let result: RequestProofResult = serde_json::from_slice(&body)?;
+    assert_eq!(result.proof_result.request_status, RequestResult::Failed);
+    assert!(proof_db.pending_callbacks().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_callback_outbox_outlives_the_store() -> Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("store");
+    let path = path.to_str().unwrap();
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(2), B256::repeat_byte(3));
+    let callback =
+        PendingCallback::new(&l2_hash, &l1_head_hash, None, "http://localhost/".to_string());
+    ProofDB::new(&path).add_callback(callback.clone())?;
+
+    // The outbox is a file next to the store, rather than a record which expires with it.
+    assert!(directory.path().join("store.outbox").is_file());
+    assert_eq!(ProofDB::new(&path).pending_callbacks(), [callback]);
+
+    Ok(())
+}
+
+#[test]
+fn test_callback_registered_again_in_delivery() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(2), B256::repeat_byte(3));
+    let url = "http://localhost/".to_string();
+    let delivered = PendingCallback::new(&l2_hash, &l1_head_hash, None, url.clone());
+    proof_db.add_callback(delivered.clone())?;
+
+    // The callback is registered again while the first one is delivered, which then does not
+    // remove or update the new one.
+    let registered = PendingCallback::new(&l2_hash, &l1_head_hash, None, url);
+    proof_db.add_callback(registered)?;
+    let registered = proof_db.pending_callbacks().pop().unwrap();
+    assert_ne!(registered.id, delivered.id);
+    proof_db.update_callback(&PendingCallback { attempts: 1, ..delivered.clone() })?;
+    proof_db.remove_callback(&delivered)?;
+    assert_eq!(proof_db.pending_callbacks(), [registered]);
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{
+    interface::{Rpc, RpcImpl},
+    proof_db::ProofDB,
+    queue::QueuedRequest,
+    types::{RequestMetadata, RequestOptions, RequestResult},
+    DEFAULT_NETWORK_RPC_URL,
+};
+use kroma_zkvm_common::types::preprocessing;
+use tempfile::TempDir;
+
+const SP1_PRIVATE_KEY: &str = "0x0101010101010101010101010101010101010101010101010101010101010101";
+
+#[test]
+fn test_cancel_request() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(1), B256::repeat_byte(2));
+
+    let request_id = B256::repeat_byte(3);
+    let metadata = RequestMetadata::new(request_id, l2_hash, l1_head_hash, None);
+    proof_db.set_request_metadata(&metadata)?;
+    proof_db.cancel_request(&request_id, &l2_hash, &l1_head_hash)?;
+    let cancelled = proof_db.get_request_metadata(&request_id).unwrap();
+    assert_eq!(cancelled.status, RequestResult::Cancelled);
+    assert_eq!(cancelled.created_at, metadata.created_at);
+
+    // A request without metadata gets it, so that the cancellation is recorded.
+    let legacy_id = B256::repeat_byte(4);
+    proof_db.set_request_id(&l2_hash, &l1_head_hash, &legacy_id)?;
+    assert_eq!(proof_db.get_request_metadata(&legacy_id), None);
+    proof_db.cancel_request(&legacy_id, &l2_hash, &l1_head_hash)?;
+    let cancelled = proof_db.get_request_metadata(&legacy_id).unwrap();
+    assert_eq!(cancelled.status, RequestResult::Cancelled);
+    assert_eq!((cancelled.l2_hash, cancelled.l1_head_hash), (l2_hash, l1_head_hash));
+
+    Ok(())
+}
+
+#[test]
+fn test_cancel_request_before_submission() -> Result<()> {
+    let directory = TempDir::new()?;
+    let rpc_impl = RpcImpl::new(
+        directory.path().join("store").to_str().unwrap(),
+        SP1_PRIVATE_KEY,
+        DEFAULT_NETWORK_RPC_URL,
+    );
+    let hashes =
+        |byte: u8| (B256::repeat_byte(byte).to_string(), B256::repeat_byte(byte + 1).to_string());
+
+    // A queued request has no request id yet, so it is taken out of the queue.
+    let (l2_hash, l1_head_hash) = hashes(1);
+    let (l2, l1_head, user_req_id) = preprocessing(&l2_hash, &l1_head_hash).unwrap();
+    let options = RequestOptions::default();
+    rpc_impl.queue.push(QueuedRequest::new(user_req_id, l2, l1_head, None, vec![], options));
+    let cancelled = rpc_impl.cancel_request(l2_hash.clone(), l1_head_hash.clone());
+    assert_eq!(cancelled, Ok(RequestResult::Cancelled));
+    assert!(rpc_impl.queue.is_empty());
+    let proof_result = rpc_impl.get_proof(l2_hash, l1_head_hash).unwrap();
+    assert_eq!(proof_result.request_status, RequestResult::Cancelled);
+
+    // So is a request waiting for its witness, which is dropped once the witness arrives.
+    let (l2_hash, l1_head_hash) = hashes(3);
+    let (_, _, user_req_id) = preprocessing(&l2_hash, &l1_head_hash).unwrap();
+    rpc_impl.queue.set_status(&user_req_id, Some(RequestResult::Processing));
+    let cancelled = rpc_impl.cancel_request(l2_hash, l1_head_hash);
+    assert_eq!(cancelled, Ok(RequestResult::Cancelled));
+    assert_eq!(rpc_impl.queue.status(&user_req_id), Some(RequestResult::Cancelled));
+
+    Ok(())
+}
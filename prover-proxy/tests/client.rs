@@ -1,20 +1,33 @@
 use alloy_primitives::B256;
 use anyhow::Result;
-use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
-use jsonrpsee_core::{client::ClientT, rpc_params};
+use jsonrpsee::{
+    http_client::{HttpClient, HttpClientBuilder},
+    ws_client::WsClientBuilder,
+};
+use jsonrpsee_core::{
+    client::{ClientT, SubscriptionClientT},
+    rpc_params,
+};
 
 use kroma_prover_proxy::{
     errors::ProverError,
-    types::{ProofResult, RequestResult as ProverRequest, SpecResult as ProverSpec, WitnessResult},
+    types::{
+        BatchItemResult, ListRequestsFilter, ListRequestsResult, ProofRequestItem, ProofResult,
+        RequestProofResult, RequestResult as ProverRequest, SpecResult as ProverSpec, StatusResult,
+        WitnessResult,
+    },
     FAULT_PROOF_ELF,
 };
 use std::time::Duration;
 
 const CLIENT_TIMEOUT_SEC: u64 = 10800;
 const DEFAULT_PROVER_RPC_ENDPOINT: &str = "http://0.0.0.0:3031";
+const DEFAULT_PROVER_WS_ENDPOINT: &str = "ws://0.0.0.0:3032";
 
 pub struct TestClient {
     prover_client: HttpClient,
+    url: String,
+    ws_url: String,
 }
 
 impl TestClient {
@@ -25,7 +38,11 @@ impl TestClient {
             .build(prover_proxy_url)
             .unwrap();
 
-        Self { prover_client }
+        Self {
+            prover_client,
+            url: prover_proxy_url.to_string(),
+            ws_url: DEFAULT_PROVER_WS_ENDPOINT.to_string(),
+        }
     }
 }
 
@@ -44,6 +61,13 @@ impl TestClient {
             .map_err(|e| anyhow::anyhow!("the server is not ready: {:?}", e))
     }
 
+    /// Returns whether `/readyz` passes, i.e. the proxy can serve requests.
+    #[allow(dead_code)]
+    pub async fn is_ready(&self) -> bool {
+        let response = reqwest::get(format!("{}/readyz", self.url)).await;
+        matches!(response, Ok(response) if response.status().is_success())
+    }
+
     #[allow(dead_code)]
     pub async fn execute_witness(&self, witness_result: &WitnessResult) -> bool {
         let prover = sp1_sdk::ProverClient::from_env();
@@ -86,4 +110,47 @@ impl TestClient {
         let params = rpc_params![l2_hash, l1_head_hash];
         self.prover_client.request("getProof", params).await.unwrap()
     }
+
+    /// Subscribes to the request and returns its first result which is not `Processing`.
+    pub async fn wait_for_proof(&self, l2_hash: B256, l1_head_hash: B256) -> ProofResult {
+        let ws_client = WsClientBuilder::default()
+            .request_timeout(Duration::from_secs(CLIENT_TIMEOUT_SEC))
+            .build(&self.ws_url)
+            .await
+            .unwrap();
+        let params = rpc_params![l2_hash, l1_head_hash];
+        let mut subscription = ws_client
+            .subscribe::<ProofResult, _>("subscribeProof", params, "unsubscribeProof")
+            .await
+            .unwrap();
+        loop {
+            let proof_result = subscription.next().await.unwrap().unwrap();
+            if proof_result.request_status != ProverRequest::Processing {
+                return proof_result;
+            }
+        }
+    }
+
+    pub async fn get_proofs(
+        &self,
+        items: &[ProofRequestItem],
+    ) -> Vec<BatchItemResult<ProofResult>> {
+        let params = rpc_params![items];
+        self.prover_client.request("getProofs", params).await.unwrap()
+    }
+
+    pub async fn get_status(&self, l2_hash: B256, l1_head_hash: B256) -> StatusResult {
+        let params = rpc_params![l2_hash, l1_head_hash];
+        self.prover_client.request("getStatus", params).await.unwrap()
+    }
+
+    pub async fn get_proof_by_request_id(&self, request_id: &str) -> RequestProofResult {
+        let params = rpc_params![request_id];
+        self.prover_client.request("getProofByRequestId", params).await.unwrap()
+    }
+
+    pub async fn list_requests(&self, filter: &ListRequestsFilter) -> ListRequestsResult {
+        let params = rpc_params![filter];
+        self.prover_client.request("listRequests", params).await.unwrap()
+    }
 }
@@ -0,0 +1,84 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{crypto::KeyRing, proof_db::ProofDB, types::RequestMetadata};
+use tempfile::TempDir;
+
+#[test]
+fn test_key_ring() -> Result<()> {
+    let key_ring =
+        KeyRing::parse(&format!("key-2:{}, key-1:0x{}", "22".repeat(32), "11".repeat(32)))?;
+    assert_eq!(key_ring.active_key_id(), "key-2");
+
+    let ciphertext = key_ring.encrypt(b"proof", b"proof:01")?;
+    assert_eq!(ciphertext.key_id, "key-2");
+    assert_ne!(ciphertext.data, b"proof");
+    assert_eq!(key_ring.decrypt(&ciphertext, b"proof:01")?, b"proof");
+    // The ciphertext is bound to the key it is stored under.
+    assert!(key_ring.decrypt(&ciphertext, b"proof:02").is_err());
+
+    assert!(KeyRing::parse("").is_err());
+    assert!(KeyRing::parse("key-1:0011").is_err());
+    assert!(KeyRing::parse(&format!("key-1:{0},key-1:{0}", "11".repeat(32))).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_encryption_and_rotation() -> Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("store");
+    let path = path.to_str().unwrap();
+    let open = |keys: Option<&str>| -> Result<ProofDB> {
+        let key_ring = keys.map(KeyRing::parse).transpose()?;
+        Ok(ProofDB::new(path).with_key_ring(key_ring))
+    };
+    let (key_1, key_2) =
+        (format!("key-1:{}", "11".repeat(32)), format!("key-2:{}", "22".repeat(32)));
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(2), B256::repeat_byte(3));
+    let metadata = RequestMetadata {
+        idempotency_key: Some("retry-1".to_string()),
+        ..RequestMetadata::new(B256::repeat_byte(1), l2_hash, l1_head_hash, None)
+    };
+    let request_id = metadata.request_id;
+
+    let proof_db = open(Some(&key_1))?;
+    proof_db.set_request_metadata(&metadata)?;
+    proof_db.set_request_id(&l2_hash, &l1_head_hash, &request_id)?;
+    proof_db.set_idempotency_key("retry-1", &l2_hash, &l1_head_hash)?;
+    proof_db.discard_request(&l2_hash, &l1_head_hash)?;
+    proof_db.quarantine(&request_id, "corrupted".to_string())?;
+    assert_eq!(proof_db.get_request_metadata(&request_id), Some(metadata.clone()));
+    drop(proof_db);
+
+    // Encrypted records, including the indexes and mappings, cannot be read without their key.
+    let proof_db = open(None)?;
+    assert_eq!(proof_db.get_request_metadata(&request_id), None);
+    assert!(proof_db.request_ids().is_empty());
+    assert_eq!(proof_db.get_idempotency_key("retry-1"), None);
+    assert!(proof_db.request_history(&l2_hash, &l1_head_hash).is_empty());
+    assert!(!proof_db.is_quarantined(&request_id));
+    drop(proof_db);
+
+    // After a new key is put first, the records of the old key are still read, and a rotation
+    // re-encrypts them with the new key.
+    let proof_db = open(Some(&format!("{},{}", key_2, key_1)))?;
+    assert_eq!(proof_db.get_request_metadata(&request_id), Some(metadata.clone()));
+    // The index, the metadata, the quarantine, the hash mapping, the history and the idempotency
+    // key are re-encrypted.
+    assert_eq!(proof_db.rotate_encryption(true)?, 6);
+    assert_eq!(proof_db.rotate_encryption(false)?, 6);
+    assert_eq!(proof_db.rotate_encryption(true)?, 0);
+    drop(proof_db);
+
+    // The old key can then be dropped.
+    let proof_db = open(Some(&key_2))?;
+    assert_eq!(proof_db.get_request_metadata(&request_id), Some(metadata));
+    assert_eq!(proof_db.request_ids(), [request_id]);
+    assert_eq!(proof_db.get_idempotency_key("retry-1"), Some((l2_hash, l1_head_hash)));
+    assert_eq!(proof_db.request_history(&l2_hash, &l1_head_hash), [request_id]);
+    assert!(proof_db.is_quarantined(&request_id));
+    drop(proof_db);
+    assert!(open(None)?.rotate_encryption(true).is_err());
+
+    Ok(())
+}
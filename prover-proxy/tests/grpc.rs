@@ -0,0 +1,84 @@
+#![cfg(feature = "grpc")]
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use jsonrpc_core::Error as JsonError;
+use kroma_prover_proxy::{
+    errors::{ProverError, ProverErrorCode},
+    grpc::{
+        proto::{self, prove_request},
+        prove_input, to_status,
+    },
+    types::{RequestOptions, RequestResult, StatusResult, WitnessEncoding, WitnessInput},
+};
+use tonic::Code;
+
+#[test]
+fn test_request_options() {
+    let options = proto::RequestOptions {
+        witness_encoding: proto::WitnessEncoding::Zstd.into(),
+        priority: Some(3),
+        labels: [("chain_id".to_string(), "255".to_string())].into_iter().collect(),
+        ..Default::default()
+    };
+    let options = RequestOptions::from(options);
+    assert_eq!(options.witness_encoding, Some(WitnessEncoding::Zstd));
+    assert_eq!(options.priority, Some(3));
+    assert_eq!(options.labels.get("chain_id").map(String::as_str), Some("255"));
+    assert!(!options.force);
+}
+
+#[test]
+fn test_status_response() {
+    let response = proto::StatusResponse::from(StatusResult::pending(RequestResult::Failed));
+    assert_eq!(response.status(), proto::RequestStatus::Failed);
+    assert_eq!(response.request_id, None);
+
+    let response = proto::StatusResponse::from(StatusResult::default());
+    assert_eq!(response.status(), proto::RequestStatus::None);
+}
+
+#[test]
+fn test_to_status() {
+    let status =
+        |code: ProverErrorCode| to_status(&ProverError::new(code, None).to_json_error_with_code());
+    assert_eq!(status(ProverErrorCode::InvalidWitness).code(), Code::InvalidArgument);
+    assert_eq!(status(ProverErrorCode::RequestNotFound).code(), Code::NotFound);
+    assert_eq!(status(ProverErrorCode::SP1NetworkError).code(), Code::Unavailable);
+    assert_eq!(to_status(&JsonError::internal_error()).code(), Code::Internal);
+}
+
+#[test]
+fn test_prove_input() {
+    let zstd = proto::RequestOptions {
+        witness_encoding: proto::WitnessEncoding::Zstd.into(),
+        ..Default::default()
+    };
+    let (witness, options) = prove_input(
+        Some(prove_request::WitnessInput::WitnessBytes(vec![1, 2, 3])),
+        Some(zstd.clone()),
+    );
+    assert_eq!(witness, Some(WitnessInput::Witness(BASE64.encode([1, 2, 3]))));
+    assert_eq!(options.unwrap().witness_encoding, Some(WitnessEncoding::Zstd));
+
+    // Raw witness bytes are read as base64, whatever string encoding is declared.
+    let (_, options) =
+        prove_input(Some(prove_request::WitnessInput::WitnessBytes(vec![1, 2, 3])), None);
+    assert_eq!(options.unwrap().witness_encoding, Some(WitnessEncoding::Base64));
+
+    let witness_result = proto::WitnessResult {
+        status: proto::RequestStatus::Completed.into(),
+        program_key: "0x01".to_string(),
+        witness: "0x02".to_string(),
+    };
+    let (witness, options) =
+        prove_input(Some(prove_request::WitnessInput::WitnessResult(witness_result)), Some(zstd));
+    let witness_result = match witness {
+        Some(WitnessInput::Result(witness_result)) => witness_result,
+        witness => panic!("The witness result was not passed on: {:?}", witness),
+    };
+    assert_eq!(witness_result.status, RequestResult::Completed);
+    assert_eq!(witness_result.witness, "0x02");
+    assert_eq!(options.unwrap().witness_encoding, Some(WitnessEncoding::Zstd));
+
+    assert_eq!(prove_input(None, None), (None, None));
+}
@@ -0,0 +1,36 @@
+use kroma_prover_proxy::{
+    health::{CheckResult, HealthReport, Workers},
+    proof_db::SCHEMA_VERSION,
+};
+use std::{collections::BTreeMap, sync::mpsc, thread};
+
+#[test]
+fn test_workers() {
+    let workers = Workers::default();
+    let (sender, receiver) = mpsc::channel::<()>();
+    workers.register("looping", thread::spawn(move || receiver.recv().unwrap_or_default()));
+    workers.register("stopped", thread::spawn(|| {}));
+
+    while workers.stopped().is_empty() {
+        thread::yield_now();
+    }
+    assert_eq!(workers.stopped(), ["stopped"]);
+    assert!(!workers.check().ok);
+
+    sender.send(()).unwrap();
+}
+
+#[test]
+fn test_health_report() {
+    let mut checks = BTreeMap::new();
+    checks.insert("store".to_string(), CheckResult::pass(format!("v{}", SCHEMA_VERSION)));
+    assert!(HealthReport::new(checks.clone()).ok);
+
+    checks.insert("network".to_string(), CheckResult::fail("No answer in 5s"));
+    let report = HealthReport::new(checks);
+    assert!(!report.ok);
+    assert_eq!(
+        serde_json::to_value(&report).unwrap()["checks"]["network"],
+        serde_json::json!({"ok": false, "detail": "No answer in 5s"})
+    );
+}
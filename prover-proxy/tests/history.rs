@@ -0,0 +1,27 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::proof_db::ProofDB;
+use tempfile::TempDir;
+
+#[test]
+fn test_discard_request() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(1), B256::repeat_byte(2));
+
+    assert_eq!(proof_db.discard_request(&l2_hash, &l1_head_hash)?, None);
+
+    let first = B256::repeat_byte(3);
+    proof_db.set_request_id(&l2_hash, &l1_head_hash, &first)?;
+    assert_eq!(proof_db.discard_request(&l2_hash, &l1_head_hash)?, Some(first));
+    assert_eq!(proof_db.get_request_id(&l2_hash, &l1_head_hash), None);
+
+    // A fresh request replaces the discarded one, which stays in the history.
+    let second = B256::repeat_byte(4);
+    proof_db.set_request_id(&l2_hash, &l1_head_hash, &second)?;
+    assert_eq!(proof_db.get_request_id(&l2_hash, &l1_head_hash), Some(second));
+    assert_eq!(proof_db.discard_request(&l2_hash, &l1_head_hash)?, Some(second));
+    assert_eq!(proof_db.request_history(&l2_hash, &l1_head_hash), vec![first, second]);
+
+    Ok(())
+}
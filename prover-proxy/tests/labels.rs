@@ -0,0 +1,43 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{
+    proof_db::ProofDB,
+    types::{ListRequestsFilter, RequestMetadata},
+};
+use std::collections::BTreeMap;
+use tempfile::TempDir;
+
+#[test]
+fn test_labels_and_idempotency_keys() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let (l2_hash, l1_head_hash) = (B256::repeat_byte(1), B256::repeat_byte(2));
+
+    assert_eq!(proof_db.get_idempotency_key("retry-1"), None);
+    proof_db.set_idempotency_key("retry-1", &l2_hash, &l1_head_hash)?;
+    assert_eq!(proof_db.get_idempotency_key("retry-1"), Some((l2_hash, l1_head_hash)));
+
+    let mut metadata = RequestMetadata::new(B256::repeat_byte(3), l2_hash, l1_head_hash, None);
+    metadata.idempotency_key = Some("retry-1".to_string());
+    metadata.labels = BTreeMap::from([
+        ("dispute_game".to_string(), "0xabcd".to_string()),
+        ("chain_id".to_string(), "255".to_string()),
+    ]);
+    proof_db.set_request_metadata(&metadata)?;
+    assert_eq!(proof_db.get_request_metadata(&metadata.request_id), Some(metadata.clone()));
+
+    let filter = |labels: &[(&str, &str)]| ListRequestsFilter {
+        labels: Some(labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()),
+        ..Default::default()
+    };
+    assert!(filter(&[("chain_id", "255")]).matches(&metadata));
+    assert!(filter(&[("chain_id", "255"), ("dispute_game", "0xabcd")]).matches(&metadata));
+    assert!(!filter(&[("chain_id", "1")]).matches(&metadata));
+    assert!(!filter(&[("operator", "0x01")]).matches(&metadata));
+
+    let by_key =
+        ListRequestsFilter { idempotency_key: Some("retry-2".into()), ..Default::default() };
+    assert!(!by_key.matches(&metadata));
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{
+    metrics::{
+        observe_rpc, observe_sp1, observe_time_to_proof, REQUESTS, RPC_CALLS, SP1_NETWORK_ERRORS,
+        SP1_STATUS_QUERY_DURATION, STORE_SIZE, TIME_TO_PROOF,
+    },
+    proof_db::ProofDB,
+    types::{RequestMetadata, RequestResult},
+};
+use sp1_sdk::network::proto::network::ProofRequest;
+use std::time::Instant;
+use tempfile::TempDir;
+
+#[test]
+fn test_rpc_metrics() {
+    let calls = |outcome: &str| RPC_CALLS.with_label_values(&["getStatus", outcome]).get();
+    let (ok, error) = (calls("ok"), calls("error"));
+    observe_rpc("getStatus", Instant::now(), true);
+    observe_rpc("getStatus", Instant::now(), false);
+    assert_eq!((calls("ok"), calls("error")), (ok + 1, error + 1));
+
+    let errors = SP1_NETWORK_ERRORS.with_label_values(&["status_query"]).get();
+    let queries = SP1_STATUS_QUERY_DURATION.get_sample_count();
+    observe_sp1("status_query", &SP1_STATUS_QUERY_DURATION, Instant::now(), &Ok::<_, ()>(()));
+    observe_sp1("status_query", &SP1_STATUS_QUERY_DURATION, Instant::now(), &Err::<(), _>(()));
+    assert_eq!(SP1_NETWORK_ERRORS.with_label_values(&["status_query"]).get(), errors + 1);
+    assert_eq!(SP1_STATUS_QUERY_DURATION.get_sample_count(), queries + 2);
+}
+
+#[test]
+fn test_request_metrics() -> Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let requests = |state: &str| REQUESTS.with_label_values(&[state]).get();
+    let (processing, completed, store_size) =
+        (requests("Processing"), requests("Completed"), STORE_SIZE.get());
+    let request_id = B256::repeat_byte(1);
+    let metadata = RequestMetadata::new(request_id, B256::ZERO, B256::ZERO, None);
+    proof_db.set_request_metadata(&metadata)?;
+    assert_eq!((requests("Processing"), STORE_SIZE.get()), (processing + 1, store_size + 1));
+
+    proof_db.update_request_status(&request_id, RequestResult::Completed)?;
+    // A status which does not change is not counted again.
+    proof_db.update_request_status(&request_id, RequestResult::Completed)?;
+    // The gauges follow the transition without reading the store.
+    assert_eq!((requests("Processing"), requests("Completed")), (processing, completed + 1));
+    assert_eq!(STORE_SIZE.get(), store_size + 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_time_to_proof() {
+    let (samples, sum) = (TIME_TO_PROOF.get_sample_count(), TIME_TO_PROOF.get_sample_sum());
+    // The time is the one the SP1 network reports, from its creation of the request.
+    observe_time_to_proof(&ProofRequest {
+        created_at: 1000,
+        fulfilled_at: Some(1600),
+        ..Default::default()
+    });
+    // A request which is not fulfilled is not observed.
+    observe_time_to_proof(&ProofRequest { created_at: 1000, ..Default::default() });
+    assert_eq!(TIME_TO_PROOF.get_sample_count(), samples + 1);
+    assert_eq!(TIME_TO_PROOF.get_sample_sum(), sum + 600.0);
+}
@@ -0,0 +1,77 @@
+use flate2::{write::GzEncoder, Compression};
+use jsonrpc_core::{futures::future, MetaIoHandler, Value};
+use jsonrpc_http_server::{
+    hyper::{
+        body::{self, Bytes},
+        header::CONTENT_ENCODING,
+        Body, Request, Response, StatusCode,
+    },
+    RequestMiddleware, RequestMiddlewareAction,
+};
+use kroma_prover_proxy::{
+    interface::{RpcImpl, RpcMetrics},
+    middleware::ProxyMiddleware,
+    rest::RestApi,
+    DEFAULT_NETWORK_RPC_URL, MAX_REQUEST_BODY_SIZE,
+};
+use std::{io::Write, sync::Arc};
+use tempfile::TempDir;
+
+const SP1_PRIVATE_KEY: &str = "0x0101010101010101010101010101010101010101010101010101010101010101";
+
+/// Returns a request whose body exceeds `MAX_REQUEST_BODY_SIZE` and has no `Content-Length`.
+fn oversized_request(path: &str, encoding: Option<&str>) -> Request<Body> {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let chunk = Bytes::from(vec![0u8; 1024 * 1024]);
+        for _ in 0..=MAX_REQUEST_BODY_SIZE / chunk.len() {
+            // The body is dropped once it is rejected.
+            if sender.send_data(chunk.clone()).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut request = Request::post(path);
+    if let Some(encoding) = encoding {
+        request = request.header(CONTENT_ENCODING, encoding);
+    }
+    request.body(body).unwrap()
+}
+
+async fn respond(middleware: &ProxyMiddleware, request: Request<Body>) -> Response<Body> {
+    match middleware.on_request(request) {
+        RequestMiddlewareAction::Respond { response, .. } => response.await.unwrap(),
+        RequestMiddlewareAction::Proceed { .. } => panic!("the request was passed on"),
+    }
+}
+
+#[tokio::test]
+async fn test_body_limits() {
+    let directory = TempDir::new().unwrap();
+    let store_path = directory.path().join("store");
+    let rpc_impl =
+        RpcImpl::new(store_path.to_str().unwrap(), SP1_PRIVATE_KEY, DEFAULT_NETWORK_RPC_URL);
+    let mut rpc = MetaIoHandler::with_middleware(RpcMetrics);
+    rpc.add_method("ping", |_| future::ready(Ok(Value::from("pong"))));
+    let middleware =
+        ProxyMiddleware::default().with_rest_api(RestApi::new(rpc_impl)).with_rpc(Arc::new(rpc));
+
+    // Bodies without `Content-Length` are limited as they are sent, compressed or not.
+    let request = oversized_request("/v1/proofs/0x01/0x02", None);
+    assert_eq!(respond(&middleware, request).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let request = oversized_request("/", Some("gzip"));
+    assert_eq!(respond(&middleware, request).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // A compressed JSON-RPC request is answered after it is decompressed.
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(br#"{"jsonrpc":"2.0","method":"ping","params":[],"id":1}"#).unwrap();
+    let request = Request::post("/")
+        .header(CONTENT_ENCODING, "gzip")
+        .body(Body::from(encoder.finish().unwrap()))
+        .unwrap();
+    let response = respond(&middleware, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["result"], "pong");
+}
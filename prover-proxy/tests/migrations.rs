@@ -0,0 +1,110 @@
+mod utils;
+
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{
+    migrations::run_migrations,
+    proof_db::{ProofDB, SCHEMA_VERSION},
+};
+use kroma_zkvm_common::db::FileDB;
+use tempfile::TempDir;
+
+static REQUEST_ID: B256 = B256::repeat_byte(1);
+static L2_HASH: B256 = B256::repeat_byte(2);
+static L1_HEAD_HASH: B256 = B256::repeat_byte(3);
+
+/// Writes a completed request the way the proxy wrote it before the schema versioning.
+fn seed_baseline(path: &str) -> Result<()> {
+    let db = FileDB::new(path.into(), 1000, 7 * 24 * 60 * 60);
+    let hashes_key = [L2_HASH.as_slice(), L1_HEAD_HASH.as_slice()].concat();
+    db.set(&hashes_key, &REQUEST_ID)?;
+    db.set(&bincode::serialize(&REQUEST_ID.to_string())?, &utils::dummy_proof(b"public values"))
+}
+
+#[test]
+fn test_migrations_from_baseline() -> Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("store");
+    let path = path.to_str().unwrap();
+    seed_baseline(path)?;
+    let proof_db = ProofDB::new(path);
+
+    // A dry run reports every step without writing anything.
+    let reports = run_migrations(&proof_db, &[REQUEST_ID], true)?;
+    assert_eq!(reports.len(), SCHEMA_VERSION as usize);
+    assert_eq!(reports[0].affected, 1);
+    assert_eq!(proof_db.schema_version()?, 0);
+    assert!(proof_db.request_ids().is_empty());
+
+    let applied = run_migrations(&proof_db, &[REQUEST_ID], false)?;
+    assert_eq!(applied[0].affected, 1);
+    assert_eq!(proof_db.schema_version()?, SCHEMA_VERSION);
+    assert!(run_migrations(&proof_db, &[REQUEST_ID], false)?.is_empty());
+
+    assert_eq!(proof_db.request_ids(), [REQUEST_ID]);
+    assert_eq!(proof_db.get_request_id(&L2_HASH, &L1_HEAD_HASH), Some(REQUEST_ID));
+    let proof = proof_db.get_proof_by_id(&REQUEST_ID).expect("the proof is upgraded");
+    assert_eq!(proof.public_values.as_slice(), b"public values");
+    // The baseline store has no metadata.
+    assert_eq!(proof_db.get_request_metadata(&REQUEST_ID), None);
+
+    // A store of a newer proxy is not downgraded.
+    proof_db.set_schema_version(SCHEMA_VERSION + 1)?;
+    assert!(run_migrations(&proof_db, &[], false).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_legacy_proofs_not_listed() -> Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("store");
+    let path = path.to_str().unwrap();
+    seed_baseline(path)?;
+    let proof_db = ProofDB::new(path);
+
+    // A baseline proof which is not among the legacy request ids is upgraded on its first read.
+    let reports = run_migrations(&proof_db, &[], false)?;
+    assert_eq!(reports[0].affected, 0);
+    assert!(proof_db.request_ids().is_empty());
+    assert!(proof_db.get_proof(&L2_HASH, &L1_HEAD_HASH).is_some());
+    assert_eq!(proof_db.request_ids(), [REQUEST_ID]);
+
+    Ok(())
+}
+
+#[test]
+fn test_expired_records() -> Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("store");
+    let path = path.to_str().unwrap();
+    let proof_db = ProofDB::new(path);
+    run_migrations(&proof_db, &[], false)?;
+    proof_db.set_request_id(&L2_HASH, &L1_HEAD_HASH, &REQUEST_ID)?;
+    proof_db.set_proof(&REQUEST_ID, &utils::dummy_proof(b"public values"))?;
+    drop(proof_db);
+
+    // A store whose records all expired keeps the files next to it.
+    let expired = directory.path().join("expired");
+    let expired = expired.to_str().unwrap();
+    for extension in ["schema", "index"] {
+        std::fs::copy(format!("{}.{}", path, extension), format!("{}.{}", expired, extension))?;
+    }
+    let proof_db = ProofDB::new(expired);
+
+    // It is still at the current schema version, so it is neither migrated again nor unhealthy.
+    assert_eq!(proof_db.schema_version()?, SCHEMA_VERSION);
+    proof_db.check_readable()?;
+    assert!(run_migrations(&proof_db, &[], false)?.is_empty());
+
+    // The expired requests are pruned from the index.
+    assert_eq!(proof_db.request_ids(), [REQUEST_ID]);
+    assert_eq!(proof_db.prune_request_ids()?, 1);
+    assert!(proof_db.request_ids().is_empty());
+    assert!(proof_db.list_requests(|_| true, None, 10).0.is_empty());
+
+    // The requests of the original store are kept.
+    assert_eq!(ProofDB::new(path).prune_request_ids()?, 0);
+
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use alloy_primitives::B256;
+use kroma_prover_proxy::{
+    errors::ProverError,
+    interface::{Rpc, RpcImpl, OPENRPC_DOCUMENT},
+    types::{
+        BatchItemResult, ListRequestsFilter, ListRequestsResult, ProofRequestItem, ProofResult,
+        ProveRequestItem, RequestMetadata, RequestOptions, RequestProofResult, RequestResult,
+        SpecResult, StatusResult, UploadStatus, WitnessEncoding, WitnessInput, WitnessResult,
+    },
+    version::API_VERSION,
+    DEFAULT_NETWORK_RPC_URL,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tempfile::TempDir;
+
+/// A well-formed key, which is never used to reach the SP1 network.
+const SP1_PRIVATE_KEY: &str = "0x0101010101010101010101010101010101010101010101010101010101010101";
+
+/// The sha256 of `openrpc.json` at each version. A change of the document fails the test until
+/// its version is bumped and the digest of the new version is appended here.
+static DIGESTS: &[(&str, &str)] = &[
+    ("v1.2.0", "7940193285181359605d707cc2aaa3faaa9256063dde8b9d32f534e8439a2eed"),
+    ("v1.3.0", "907f9ec5f20a53ca176b9f4422bf06affa70f588717becee3b5522395a51d352"),
+];
+
+fn document() -> Value {
+    serde_json::from_str(OPENRPC_DOCUMENT).unwrap()
+}
+
+#[test]
+fn test_openrpc_version() {
+    let document = document();
+    let version = document["info"]["version"].as_str().unwrap();
+    assert_eq!(version, API_VERSION);
+
+    let digest = hex::encode(Sha256::digest(OPENRPC_DOCUMENT.as_bytes()));
+    let recorded = DIGESTS.iter().find(|(v, _)| *v == version).map(|(_, digest)| *digest);
+    assert_eq!(
+        recorded,
+        Some(digest.as_str()),
+        "openrpc.json changed without a version bump, or the digest of {} is not recorded",
+        version
+    );
+}
+
+#[test]
+fn test_openrpc_methods() {
+    // NOTE(Ethan): the method names are read from the delegate of the `Rpc` trait, so that a new
+    // method cannot be left out of the document.
+    let directory = TempDir::new().unwrap();
+    let store_path = directory.path().join("store");
+    let rpc_impl =
+        RpcImpl::new(store_path.to_str().unwrap(), SP1_PRIVATE_KEY, DEFAULT_NETWORK_RPC_URL);
+    let mut declared: Vec<String> = rpc_impl
+        .to_delegate()
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| name != "rpc.discover")
+        .collect();
+    declared.sort();
+
+    let document = document();
+    let mut documented: Vec<&str> = document["methods"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|method| method["name"].as_str().unwrap())
+        .collect();
+    documented.sort();
+    assert_eq!(declared, documented);
+}
+
+/// Checks `value` against `schema`, resolving the `$ref`s in `document`. Unlike a JSON Schema
+/// validator, an object must have exactly the declared properties, so that a field added to or
+/// removed from a type is caught too.
+fn check(document: &Value, schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let schema = document
+            .pointer(reference.trim_start_matches('#'))
+            .ok_or_else(|| format!("{}: {} is not defined", path, reference))?;
+        return check(document, schema, value, path);
+    }
+    if let Some(schemas) = schema["oneOf"].as_array() {
+        let matched =
+            schemas.iter().filter(|schema| check(document, schema, value, path).is_ok()).count();
+        if matched != 1 {
+            return Err(format!("{}: {} schemas of oneOf match {}", path, matched, value));
+        }
+        return Ok(());
+    }
+    if let Some(variants) = schema["enum"].as_array() {
+        if !variants.contains(value) {
+            return Err(format!("{}: {} is not one of {:?}", path, value, variants));
+        }
+    }
+    let types: Vec<&str> = match &schema["type"] {
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        Value::String(ty) => vec![ty.as_str()],
+        _ => vec![],
+    };
+    if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+        return Err(format!("{}: {} is not {:?}", path, value, types));
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(properties) = schema["properties"].as_object() {
+                let mut fields: Vec<&String> = object.keys().collect();
+                let mut declared: Vec<&String> = properties.keys().collect();
+                fields.sort();
+                declared.sort();
+                if fields != declared {
+                    return Err(format!("{}: has {:?} but declares {:?}", path, fields, declared));
+                }
+                for (name, value) in object {
+                    check(document, &properties[name], value, &format!("{}.{}", path, name))?;
+                }
+            }
+            if let Some(schema) = schema.get("additionalProperties") {
+                for (name, value) in object {
+                    check(document, schema, value, &format!("{}.{}", path, name))?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(document, schema, item, &format!("{}[{}]", path, index))?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap()
+}
+
+fn options() -> RequestOptions {
+    RequestOptions {
+        callback_url: Some("https://example.com/callback".to_string()),
+        witness_encoding: Some(WitnessEncoding::Zstd),
+        force: true,
+        priority: Some(1),
+        deadline: Some(1_700_000_000),
+        idempotency_key: Some("retry-1".to_string()),
+        labels: BTreeMap::from([("chain_id".to_string(), "255".to_string())]),
+    }
+}
+
+fn metadata() -> RequestMetadata {
+    RequestMetadata {
+        caller: Some("challenger".to_string()),
+        deadline: Some(1_700_000_000),
+        idempotency_key: Some("retry-1".to_string()),
+        labels: options().labels,
+        ..RequestMetadata::new(
+            B256::repeat_byte(3),
+            B256::repeat_byte(1),
+            B256::repeat_byte(2),
+            None,
+        )
+    }
+}
+
+/// Returns example params and an example result of `method`, serialized from the types of
+/// `Rpc`.
+fn example(method: &str) -> (Vec<Value>, Value) {
+    let hash = to_value(B256::repeat_byte(1));
+    let error = ProverError::request_not_found("Not found".to_string()).to_json_error_with_code();
+    let upload = to_value(UploadStatus { session_id: "0x01".to_string(), received: 4 });
+    let proof_result = ProofResult::processing(B256::repeat_byte(3).to_string());
+    match method {
+        "spec" => (vec![], to_value(SpecResult::default())),
+        "requestProve" => (
+            vec![
+                hash.clone(),
+                hash,
+                to_value(WitnessInput::Result(WitnessResult::default())),
+                to_value(options()),
+            ],
+            to_value(RequestResult::Processing),
+        ),
+        "requestProveBatch" => {
+            let item = ProveRequestItem {
+                l2_hash: B256::repeat_byte(1).to_string(),
+                l1_head_hash: B256::repeat_byte(2).to_string(),
+                witness: Some(WitnessInput::Witness("0x".to_string())),
+                options: Some(options()),
+            };
+            let results = vec![
+                BatchItemResult { result: Some(RequestResult::Processing), error: None },
+                BatchItemResult { result: None, error: Some(error) },
+            ];
+            (vec![to_value(vec![item])], to_value(results))
+        }
+        "beginUpload" => (vec![hash.clone(), hash, to_value(options())], upload),
+        "appendUpload" => (vec![json!("0x01"), json!(0), json!("0x")], upload),
+        "getUpload" => (vec![json!("0x01")], upload),
+        "commitUpload" => (vec![json!("0x01"), hash], to_value(RequestResult::Processing)),
+        "getProof" => (vec![hash.clone(), hash], to_value(proof_result)),
+        "getProofs" => {
+            let item = ProofRequestItem {
+                l2_hash: B256::repeat_byte(1).to_string(),
+                l1_head_hash: B256::repeat_byte(2).to_string(),
+            };
+            let results = vec![
+                BatchItemResult { result: Some(proof_result), error: None },
+                BatchItemResult { result: None, error: Some(error) },
+            ];
+            (vec![to_value(vec![item])], to_value(results))
+        }
+        "getProofByRequestId" => {
+            let result =
+                RequestProofResult::new(&B256::repeat_byte(1), &B256::repeat_byte(2), proof_result);
+            (vec![hash], to_value(result))
+        }
+        "subscribeProof" => (vec![hash.clone(), hash], json!(1)),
+        "unsubscribeProof" => (vec![json!(1)], json!(true)),
+        "getStatus" => (vec![hash.clone(), hash], to_value(StatusResult::new(&metadata(), 600))),
+        "cancelRequest" => (vec![hash.clone(), hash], to_value(RequestResult::Cancelled)),
+        "listRequests" => {
+            let filter = ListRequestsFilter {
+                status: Some(RequestResult::Processing),
+                from: Some(1_600_000_000),
+                to: Some(1_700_000_000),
+                program_key: Some(B256::repeat_byte(4)),
+                caller: Some("challenger".to_string()),
+                idempotency_key: Some("retry-1".to_string()),
+                labels: Some(options().labels),
+                cursor: Some(B256::repeat_byte(3)),
+                limit: Some(10),
+            };
+            let result =
+                ListRequestsResult { requests: vec![metadata()], next_cursor: Some(B256::ZERO) };
+            (vec![to_value(filter)], to_value(result))
+        }
+        _ => panic!("{} has no example", method),
+    }
+}
+
+#[test]
+fn test_openrpc_schemas() {
+    // NOTE(Ethan): the document is written by hand, so the values of the types are checked
+    // against it to catch a type which changes without the document.
+    let document = document();
+    for method in document["methods"].as_array().unwrap() {
+        let name = method["name"].as_str().unwrap();
+        let (params, result) = example(name);
+        let declared = method["params"].as_array().unwrap();
+        assert_eq!(params.len(), declared.len(), "the params of {}", name);
+        for (param, declared) in params.iter().zip(declared) {
+            let path = format!("{}.{}", name, declared["name"].as_str().unwrap());
+            check(&document, &declared["schema"], param, &path).unwrap();
+        }
+        check(&document, &method["result"]["schema"], &result, name).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+use alloy_primitives::B256;
+use kroma_prover_proxy::{
+    proof_db::ProofDB,
+    types::{RequestMetadata, RequestResult},
+};
+use tempfile::TempDir;
+
+#[test]
+fn test_list_requests_pagination() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    for byte in 1..=5 {
+        let mut metadata =
+            RequestMetadata::new(B256::repeat_byte(byte), B256::ZERO, B256::ZERO, None);
+        if byte % 2 == 0 {
+            metadata.status = RequestResult::Completed;
+        }
+        proof_db.set_request_metadata(&metadata)?;
+    }
+    let ids = |requests: &[RequestMetadata]| -> Vec<B256> {
+        requests.iter().map(|metadata| metadata.request_id).collect()
+    };
+
+    // Pages are newest first, and the cursor of the last page is `None`.
+    let (page, cursor) = proof_db.list_requests(|_| true, None, 2);
+    assert_eq!(ids(&page), [B256::repeat_byte(5), B256::repeat_byte(4)]);
+    assert_eq!(cursor, Some(B256::repeat_byte(4)));
+    let (page, cursor) = proof_db.list_requests(|_| true, cursor.as_ref(), 2);
+    assert_eq!(ids(&page), [B256::repeat_byte(3), B256::repeat_byte(2)]);
+    let (page, cursor) = proof_db.list_requests(|_| true, cursor.as_ref(), 2);
+    assert_eq!(ids(&page), [B256::repeat_byte(1)]);
+    assert_eq!(cursor, None);
+
+    // A page which ends with the last match has no cursor, even if other requests follow.
+    let completed = |metadata: &RequestMetadata| metadata.status == RequestResult::Completed;
+    let (page, cursor) = proof_db.list_requests(completed, None, 1);
+    assert_eq!(ids(&page), [B256::repeat_byte(4)]);
+    let (page, cursor) = proof_db.list_requests(completed, cursor.as_ref(), 1);
+    assert_eq!(ids(&page), [B256::repeat_byte(2)]);
+    assert_eq!(cursor, None);
+
+    // An unknown cursor yields nothing rather than the first page.
+    let (page, cursor) = proof_db.list_requests(|_| true, Some(&B256::repeat_byte(9)), 2);
+    assert!(page.is_empty());
+    assert_eq!(cursor, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_estimated_proving_secs() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let proof_db = ProofDB::new(directory.path().join("store").to_str().unwrap());
+    let complete = |byte: u8, proving_secs: u64| -> anyhow::Result<()> {
+        let mut metadata =
+            RequestMetadata::new(B256::repeat_byte(byte), B256::ZERO, B256::ZERO, None);
+        metadata.created_at -= proving_secs;
+        proof_db.set_request_metadata(&metadata)?;
+        proof_db.update_request_status(&metadata.request_id, RequestResult::Completed)
+    };
+
+    // The first estimate is read from the stored requests.
+    complete(1, 100)?;
+    let estimate = proof_db.estimated_proving_secs();
+    assert!((100..=101).contains(&estimate), "{}", estimate);
+
+    // Later completions update it without reading the store again.
+    complete(2, 300)?;
+    let estimate = proof_db.estimated_proving_secs();
+    assert!((200..=201).contains(&estimate), "{}", estimate);
+
+    Ok(())
+}
+
+#[test]
+fn test_store_lock() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("store");
+    let server = ProofDB::new(path.to_str().unwrap());
+    let command = ProofDB::new(path.to_str().unwrap());
+
+    // A command cannot lock the store while a server holds it, until the server releases it.
+    let lock = server.lock()?;
+    assert!(command.lock().is_err());
+    drop(lock);
+    let _lock = command.lock()?;
+    assert!(server.lock().is_err());
+
+    // Other stores are not locked.
+    let other = ProofDB::new(directory.path().join("other").to_str().unwrap());
+    other.lock()?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use alloy_primitives::B256;
+use kroma_prover_proxy::{
+    queue::{QueuedRequest, SubmissionQueue},
+    types::{RequestOptions, RequestResult},
+    utils::{fulfillment_plan, now_secs},
+};
+use sp1_sdk::network::FulfillmentStrategy;
+
+fn request(user_req_id: &str, priority: Option<u32>, deadline: Option<u64>) -> QueuedRequest {
+    let options = RequestOptions { priority, deadline, ..Default::default() };
+    QueuedRequest::new(user_req_id.to_string(), B256::ZERO, B256::ZERO, None, vec![], options)
+}
+
+#[test]
+fn test_submission_order() {
+    let queue = SubmissionQueue::default();
+    queue.push(request("first", None, None));
+    queue.push(request("later_deadline", Some(1), Some(2000)));
+    queue.push(request("second", None, None));
+    queue.push(request("earlier_deadline", Some(1), Some(1000)));
+    queue.push(request("urgent", Some(9), None));
+    assert_eq!(queue.status("first"), Some(RequestResult::Processing));
+
+    let order: Vec<String> = (0..queue.len()).map(|_| queue.pop().user_req_id).collect();
+    assert_eq!(order, ["urgent", "earlier_deadline", "later_deadline", "first", "second"]);
+    assert!(queue.is_empty());
+
+    // A popped request stays `Processing` until its submitter is done with it.
+    assert_eq!(queue.status("second"), Some(RequestResult::Processing));
+    queue.set_status("second", None);
+    assert_eq!(queue.status("second"), None);
+}
+
+#[test]
+fn test_cancel() {
+    let queue = SubmissionQueue::default();
+    queue.push(request("first", None, None));
+    queue.push(request("second", None, None));
+    assert!(queue.cancel("first"));
+    assert_eq!(queue.status("first"), Some(RequestResult::Cancelled));
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue.pop().user_req_id, "second");
+
+    // Only a request which is `Processing` can be cancelled.
+    assert!(!queue.cancel("first"));
+    assert!(!queue.cancel("unknown"));
+    queue.set_status("failed", Some(RequestResult::Failed));
+    assert!(!queue.cancel("failed"));
+}
+
+#[test]
+fn test_fulfillment_plan() {
+    let (strategy, timeout_secs) = fulfillment_plan(None, 3600);
+    assert_eq!((strategy, timeout_secs), (FulfillmentStrategy::Hosted, 7200));
+
+    // A distant deadline does not stretch the timeout.
+    let (strategy, timeout_secs) = fulfillment_plan(Some(now_secs() + 86400), 3600);
+    assert_eq!((strategy, timeout_secs), (FulfillmentStrategy::Hosted, 7200));
+
+    // A close deadline bounds the timeout and goes to the reserved capacity.
+    let (strategy, timeout_secs) = fulfillment_plan(Some(now_secs() + 3000), 3600);
+    assert_eq!(strategy, FulfillmentStrategy::Reserved);
+    assert!(timeout_secs <= 3000 && timeout_secs >= 2990);
+}
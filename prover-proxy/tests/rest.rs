@@ -0,0 +1,66 @@
+use alloy_primitives::B256;
+use jsonrpc_core::Error as JsonError;
+use jsonrpc_http_server::hyper::{Method, StatusCode};
+use kroma_prover_proxy::{
+    errors::{ProverError, ProverErrorCode},
+    rest::{status_of, Route, OPENAPI_DOCUMENT},
+};
+
+#[test]
+fn test_route() {
+    let (l2_hash, l1_head_hash, request_id) = (
+        B256::repeat_byte(1).to_string(),
+        B256::repeat_byte(2).to_string(),
+        B256::repeat_byte(3).to_string(),
+    );
+    let proofs = format!("/v1/proofs/{}/{}", l2_hash, l1_head_hash);
+    assert_eq!(Route::parse(&Method::GET, "/v1/spec"), Ok(Route::Spec));
+    assert_eq!(
+        Route::parse(&Method::POST, &proofs),
+        Ok(Route::RequestProve { l2_hash: l2_hash.clone(), l1_head_hash: l1_head_hash.clone() })
+    );
+    assert_eq!(
+        Route::parse(&Method::DELETE, &format!("{}/", proofs)),
+        Ok(Route::CancelRequest { l2_hash: l2_hash.clone(), l1_head_hash: l1_head_hash.clone() })
+    );
+    assert_eq!(
+        Route::parse(&Method::GET, &format!("{}/status", proofs)),
+        Ok(Route::GetStatus { l2_hash, l1_head_hash })
+    );
+    assert_eq!(
+        Route::parse(&Method::GET, &format!("/v1/requests/{}", request_id)),
+        Ok(Route::GetProofByRequestId { request_id })
+    );
+
+    // Invalid hashes are rejected before the method is called.
+    assert_eq!(Route::parse(&Method::GET, "/v1/proofs/0x01/0x02"), Err(StatusCode::BAD_REQUEST));
+    assert_eq!(Route::parse(&Method::GET, "/v1/requests/0x03"), Err(StatusCode::BAD_REQUEST));
+
+    assert_eq!(Route::parse(&Method::POST, "/v1/spec"), Err(StatusCode::METHOD_NOT_ALLOWED));
+    assert_eq!(Route::parse(&Method::GET, "/v1/proofs"), Err(StatusCode::NOT_FOUND));
+    assert!(Route::is_rest_path("/openapi.json"));
+    assert!(!Route::is_rest_path("/"));
+}
+
+#[test]
+fn test_status_of() {
+    let status =
+        |code: ProverErrorCode| status_of(&ProverError::new(code, None).to_json_error_with_code());
+    assert_eq!(status(ProverErrorCode::InvalidInputHash), StatusCode::BAD_REQUEST);
+    assert_eq!(status(ProverErrorCode::SP1NetworkError), StatusCode::BAD_GATEWAY);
+    assert_eq!(status(ProverErrorCode::RequestNotFound), StatusCode::NOT_FOUND);
+    assert_eq!(status_of(&JsonError::invalid_params("")), StatusCode::BAD_REQUEST);
+    assert_eq!(status_of(&JsonError::internal_error()), StatusCode::INTERNAL_SERVER_ERROR);
+
+    // Errors without a code in their data are internal.
+    let error = ProverError::new(ProverErrorCode::RequestNotFound, None).to_json_error();
+    assert_eq!(error.data, None);
+    assert_eq!(status_of(&error), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn test_openapi_document() {
+    let document: serde_json::Value = serde_json::from_str(OPENAPI_DOCUMENT).unwrap();
+    let paths = document["paths"].as_object().unwrap();
+    assert!(paths.contains_key("/v1/proofs/{l2_hash}/{l1_head_hash}/status"));
+}
@@ -4,7 +4,9 @@ mod utils;
 use alloy_primitives::{b256, B256};
 use anyhow::Result;
 use client::TestClient;
-use kroma_prover_proxy::types::RequestResult as ProverRequest;
+use kroma_prover_proxy::types::{
+    ListRequestsFilter, ProofRequestItem, RequestResult as ProverRequest,
+};
 use std::{thread::sleep, time::Duration};
 use utils::{load_witness, ProofFixture};
 
@@ -44,6 +46,11 @@ async fn scenario(
         client.request_prove(l2_hash, l1_head_hash, &witness_result).await.unwrap();
     assert_eq!(request_result, ProverRequest::Processing);
 
+    // The status is served from the store along with an estimated completion time.
+    let status_result = client.get_status(l2_hash, l1_head_hash).await;
+    assert_eq!(status_result.status, Some(ProverRequest::Processing));
+    assert!(status_result.estimated_completion_at.is_some());
+
     let proof_result = loop {
         let proof_result = client.get_proof(l2_hash, l1_head_hash).await;
         if proof_result.request_status == ProverRequest::Completed {
@@ -55,6 +62,35 @@ async fn scenario(
         sleep(Duration::from_secs(20));
     };
 
+    // The final result is pushed to a subscriber as well.
+    let pushed_result = client.wait_for_proof(l2_hash, l1_head_hash).await;
+    assert_eq!(pushed_result, proof_result);
+
+    // The stored proof can be looked up by its request id as well.
+    let request_proof_result = client.get_proof_by_request_id(&proof_result.request_id).await;
+    assert_eq!(request_proof_result.l2_hash, l2_hash.to_string());
+    assert_eq!(request_proof_result.l1_head_hash, l1_head_hash.to_string());
+    assert_eq!(request_proof_result.proof_result, proof_result);
+
+    // A batch returns a result or an error per item, in order.
+    let items = [
+        ProofRequestItem { l2_hash: l2_hash.to_string(), l1_head_hash: l1_head_hash.to_string() },
+        ProofRequestItem { l2_hash: "0x".to_string(), l1_head_hash: l1_head_hash.to_string() },
+    ];
+    let batch_result = client.get_proofs(&items).await;
+    assert_eq!(batch_result.len(), 2);
+    assert_eq!(batch_result[0].result.as_ref(), Some(&proof_result));
+    assert!(batch_result[1].result.is_none() && batch_result[1].error.is_some());
+
+    // The request is listed as completed without its proof.
+    let filter =
+        ListRequestsFilter { status: Some(ProverRequest::Completed), ..Default::default() };
+    let list_result = client.list_requests(&filter).await;
+    assert!(list_result
+        .requests
+        .iter()
+        .any(|metadata| metadata.l2_hash == l2_hash && metadata.l1_head_hash == l1_head_hash));
+
     ProofFixture::save_proof(proof_data, &proof_result).unwrap();
 }
 
@@ -68,7 +104,7 @@ async fn test_online_scenario() -> Result<()> {
         .spawn()?;
 
     let client = TestClient::default();
-    while client.prover_spec().await.is_err() {
+    while !client.is_ready().await {
         tokio::time::sleep(Duration::from_secs(1)).await;
     }
     println!("Prover proxy is ready.");
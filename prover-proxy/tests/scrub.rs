@@ -0,0 +1,47 @@
+mod utils;
+
+use alloy_primitives::B256;
+use anyhow::Result;
+use kroma_prover_proxy::{
+    proof_db::{Envelope, ProofDB},
+    scrub::{scrub, Verification},
+};
+use kroma_zkvm_common::db::FileDB;
+use tempfile::TempDir;
+
+#[test]
+fn test_scrub_quarantines_corrupted_proofs() -> Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("store");
+    let path = path.to_str().unwrap();
+    let (intact, corrupted) = (B256::repeat_byte(1), B256::repeat_byte(2));
+    let proof_db = ProofDB::new(path);
+    proof_db.set_proof(&intact, &utils::dummy_proof(b"intact"))?;
+    proof_db.set_proof(&corrupted, &utils::dummy_proof(b"corrupted"))?;
+    drop(proof_db);
+
+    // Flip a byte of the sealed payload, so that the record fails its checksum.
+    let db = FileDB::new(path.into(), 1000, 7 * 24 * 60 * 60);
+    let key = [b"proof:".as_slice(), corrupted.as_slice()].concat();
+    let mut envelope: Envelope = db.get(&key).expect("the proof is stored");
+    *envelope.body.last_mut().unwrap() ^= 0xff;
+    db.set(&key, &envelope)?;
+    drop(db);
+
+    // Checksums are checked without verifying any proof.
+    let proof_db = ProofDB::new(path);
+    let report = scrub(&proof_db, Verification::Batch(0), 0);
+    assert_eq!((report.checked, report.verified), (2, 0));
+    assert_eq!(report.quarantined, [corrupted]);
+    assert!(proof_db.is_quarantined(&corrupted));
+    assert!(!proof_db.is_quarantined(&intact));
+    assert!(proof_db.get_proof_by_id(&corrupted).is_none());
+    // The quarantine is a file next to the store, rather than a record which expires with it.
+    assert!(directory.path().join("store.quarantine").is_file());
+
+    // A quarantined proof is reported once.
+    assert!(scrub(&proof_db, Verification::Batch(0), 0).quarantined.is_empty());
+    assert_eq!(proof_db.quarantined_proofs().len(), 1);
+
+    Ok(())
+}
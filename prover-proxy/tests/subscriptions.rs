@@ -0,0 +1,54 @@
+use jsonrpc_core::futures::channel::mpsc;
+use jsonrpc_pubsub::{typed::Subscriber, Session};
+use kroma_prover_proxy::{subscriptions::ProofSubscriptions, types::ProofResult};
+use std::sync::Arc;
+
+#[test]
+fn test_proof_subscriptions() {
+    let subscriptions = Arc::new(ProofSubscriptions::default());
+    let (subscriber, _id, mut notifications) = Subscriber::<ProofResult>::new_test("proof");
+    let id = subscriptions.next_id();
+    let sink = subscriber.assign_id(id.clone()).unwrap();
+    subscriptions.add(id.clone(), "0x01".to_string(), "0x02".to_string(), sink);
+
+    // The current status is pushed once, and again only when it changes.
+    let processing = |_: &str, _: &str| Ok(ProofResult::processing("0x03".to_string()));
+    subscriptions.poll(processing);
+    subscriptions.poll(processing);
+    assert!(notifications.try_next().unwrap().unwrap().contains("\"Processing\""));
+    assert!(notifications.try_next().is_err());
+
+    // The final result ends the subscription.
+    subscriptions.poll(|_, _| Ok(ProofResult::failed("0x03".to_string())));
+    assert!(notifications.try_next().unwrap().unwrap().contains("\"Failed\""));
+    assert!(subscriptions.is_empty());
+    assert!(!subscriptions.remove(&id));
+
+    // An unsubscribed request is no longer fetched.
+    let (subscriber, _id, _notifications) = Subscriber::<ProofResult>::new_test("proof");
+    let id = subscriptions.next_id();
+    subscriptions.add(
+        id.clone(),
+        "0x01".to_string(),
+        "0x02".to_string(),
+        subscriber.assign_id(id.clone()).unwrap(),
+    );
+    assert!(subscriptions.remove(&id));
+    subscriptions.poll(|_, _| panic!("no request is watched"));
+}
+
+#[test]
+fn test_subscription_removed_on_close() {
+    let subscriptions = Arc::new(ProofSubscriptions::default());
+    let (transport, _receiver) = mpsc::unbounded();
+    let session = Session::new(transport);
+    let (subscriber, _id, _notifications) = Subscriber::<ProofResult>::new_test("proof");
+    let id = subscriptions.next_id();
+    let sink = subscriber.assign_id(id.clone()).unwrap();
+    subscriptions.add(id.clone(), "0x01".to_string(), "0x02".to_string(), sink);
+    subscriptions.remove_on_close(id, &session);
+
+    // A subscriber whose request never progresses is never notified, but its connection closes.
+    drop(session);
+    assert!(subscriptions.is_empty());
+}
@@ -0,0 +1,46 @@
+use alloy_primitives::keccak256;
+use anyhow::Result;
+use kroma_prover_proxy::{crypto::KeyRing, uploads::UploadStore};
+use tempfile::TempDir;
+
+#[test]
+fn test_chunked_upload() -> Result<()> {
+    let directory = TempDir::new()?;
+    let keys = format!("key-1:{}", "11".repeat(32));
+    let uploads = UploadStore::new(directory.path(), Some(KeyRing::parse(&keys)?));
+
+    let witness = format!("0x{}", "ab".repeat(10_000));
+    let session = uploads.begin("0x01".to_string(), "0x02".to_string(), None)?;
+    let session_id = session.session_id;
+    let chunks: Vec<&[u8]> = witness.as_bytes().chunks(3000).collect();
+
+    uploads.append(&session_id, 0, chunks[0])?;
+    // A retried chunk is ignored, and a gap is rejected.
+    assert_eq!(uploads.append(&session_id, 0, chunks[0])?.received, 3000);
+    assert!(uploads.append(&session_id, 6000, chunks[2]).is_err());
+    // So is a chunk at an offset which overflows.
+    assert!(uploads.append(&session_id, u64::MAX, chunks[1]).is_err());
+
+    // The upload resumes from what is received, e.g. after the proxy restarts.
+    let uploads = UploadStore::new(directory.path(), Some(KeyRing::parse(&keys)?));
+    let mut offset = uploads.get(&session_id)?.unwrap().received;
+    for chunk in &chunks[1..] {
+        offset = uploads.append(&session_id, offset, chunk)?.received;
+    }
+    assert_eq!(offset, witness.len() as u64);
+
+    // Chunks are encrypted on disk.
+    let data = std::fs::read(directory.path().join(format!("{}.data", session_id)))?;
+    assert!(!data.windows(64).any(|window| window == &witness.as_bytes()[2..66]));
+
+    assert!(uploads.assemble(&session_id, &keccak256(b"other")).is_err());
+    let (session, assembled) = uploads.assemble(&session_id, &keccak256(witness.as_bytes()))?;
+    assert_eq!(session.l2_hash, "0x01");
+    assert_eq!(assembled, witness.as_bytes());
+
+    uploads.remove(&session_id);
+    assert!(uploads.get(&session_id)?.is_none());
+    assert!(uploads.get("../proof_store").is_err());
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
+#![allow(dead_code)]
+
 use anyhow::Result;
 use kroma_prover_proxy::types::{ProofResult, WitnessResult};
 use serde::{Deserialize, Serialize};
+use sp1_sdk::{SP1Proof, SP1ProofWithPublicValues, SP1PublicValues, SP1_CIRCUIT_VERSION};
 use std::{fs::File, io::Write};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,3 +41,23 @@ pub fn load_witness<T: ToString>(witness_data: T) -> Result<WitnessResult> {
 
     Ok(witness_result)
 }
+
+/// Returns a proof which is cheap to build and to store, but which does not verify.
+pub fn dummy_proof(public_values: &[u8]) -> SP1ProofWithPublicValues {
+    // NOTE(Ethan): `PlonkBn254Proof` is not exported by `sp1_sdk`, and a Plonk proof is needed for
+    // its bytes to be served. An empty one has empty bytes, like a mock proof.
+    let proof: SP1Proof = serde_json::from_value(serde_json::json!({
+        "Plonk": {
+            "public_inputs": ["", ""],
+            "encoded_proof": "",
+            "raw_proof": "",
+            "plonk_vkey_hash": vec![0u8; 32],
+        }
+    }))
+    .expect("the mock Plonk proof is well-formed");
+    SP1ProofWithPublicValues {
+        proof,
+        public_values: SP1PublicValues::from(public_values),
+        sp1_version: SP1_CIRCUIT_VERSION.to_string(),
+    }
+}
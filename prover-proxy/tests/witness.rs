@@ -0,0 +1,55 @@
+use anyhow::Result;
+use kroma_prover_proxy::types::{RequestResult, WitnessEncoding, WitnessInput, WitnessResult};
+
+#[test]
+fn test_witness_encodings() -> Result<()> {
+    let buf = vec![vec![1, 2, 3], vec![7; 4096], vec![]];
+    let hex = WitnessResult::new_from_witness_buf(RequestResult::Completed, buf.clone());
+
+    for encoding in [
+        WitnessEncoding::Hex,
+        WitnessEncoding::Base64,
+        WitnessEncoding::Zstd,
+        WitnessEncoding::Gzip,
+    ] {
+        let witness_result = WitnessResult::new_from_witness_buf_with_encoding(
+            RequestResult::Completed,
+            buf.clone(),
+            encoding,
+        )?;
+        assert_eq!(witness_result.get_witness_buf_with_encoding(encoding)?, buf);
+
+        if encoding != WitnessEncoding::Hex {
+            assert!(witness_result.witness.len() < hex.witness.len());
+        }
+    }
+
+    // The default encoding is what the witness generator returns.
+    assert_eq!(hex.get_witness_buf_with_encoding(WitnessEncoding::default())?, buf);
+
+    // A malformed witness is an error rather than a panic.
+    assert!(WitnessResult::decode_witness_buf("0xzz", WitnessEncoding::Hex).is_err());
+    assert!(WitnessResult::decode_witness_buf("not base64!", WitnessEncoding::Zstd).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_witness_input() -> Result<()> {
+    let witness_result = WitnessResult::new_from_witness_buf(RequestResult::Completed, vec![]);
+
+    // Both the witness string and the whole `WitnessResult` are accepted.
+    let input: WitnessInput = serde_json::from_value(serde_json::json!(witness_result.witness))?;
+    assert_eq!(input.into_witness()?, witness_result.witness);
+    let input: WitnessInput = serde_json::from_value(serde_json::to_value(&witness_result)?)?;
+    assert_eq!(input.into_witness()?, witness_result.witness);
+
+    // A witness of another program or one which is not completed is rejected.
+    let other_program =
+        WitnessResult { program_key: format!("0x{}", "00".repeat(32)), ..witness_result.clone() };
+    assert!(WitnessInput::Result(other_program).into_witness().is_err());
+    let processing = WitnessResult { status: RequestResult::Processing, ..witness_result };
+    assert!(WitnessInput::Result(processing).into_witness().is_err());
+
+    Ok(())
+}
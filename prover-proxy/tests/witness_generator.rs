@@ -0,0 +1,38 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use jsonrpc_core::{IoHandler, Params};
+use jsonrpc_http_server::ServerBuilder;
+use kroma_prover_proxy::{
+    types::{RequestResult, WitnessResult},
+    witness_generator::WitnessGenerator,
+};
+
+/// A stand-in for the witness generator, which has the witness ready for any hashes.
+fn serve(witness_result: WitnessResult) -> jsonrpc_http_server::Server {
+    let mut io = IoHandler::default();
+    io.add_sync_method("requestWitness", |_: Params| {
+        Ok(serde_json::to_value(RequestResult::Processing).unwrap())
+    });
+    io.add_sync_method("getWitness", move |_: Params| {
+        Ok(serde_json::to_value(&witness_result).unwrap())
+    });
+    ServerBuilder::new(io).start_http(&"127.0.0.1:0".parse().unwrap()).unwrap()
+}
+
+#[test]
+fn test_fetch_witness() -> Result<()> {
+    let buf = vec![vec![1, 2, 3], vec![4; 1024]];
+    let server = serve(WitnessResult::new_from_witness_buf(RequestResult::Completed, buf.clone()));
+    let witness_generator = WitnessGenerator::new(&format!("http://{}", server.address()))?;
+
+    let witness_result = witness_generator.fetch(&B256::ZERO, &B256::ZERO)?;
+    assert_eq!(witness_result.status, RequestResult::Completed);
+    assert_eq!(witness_result.get_witness_buf(), buf);
+
+    // The generator failing is an error rather than a hang.
+    let server = serve(WitnessResult::new_from_witness_buf(RequestResult::Failed, vec![]));
+    let witness_generator = WitnessGenerator::new(&format!("http://{}", server.address()))?;
+    assert!(witness_generator.fetch(&B256::ZERO, &B256::ZERO).is_err());
+
+    Ok(())
+}